@@ -5,7 +5,6 @@ use std::{
 
 use egui_glow::EguiGlow;
 use egui_winit::winit;
-use log::info;
 use winit::application::ApplicationHandler;
 use winit::event_loop::ActiveEventLoop;
 
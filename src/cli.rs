@@ -0,0 +1,258 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use log::LevelFilter;
+
+use crate::{
+    graph::{GraphDesc, LoadError},
+    headless::Format,
+};
+
+pub const USAGE: &str = "\
+usage: final-project [--headless] [GRAPH] [OPTIONS]
+
+Opens GRAPH in a window, or with --headless runs its pipeline and prints the results.
+GRAPH defaults to graph_desc.toml in the data directory.
+
+options:
+  --data DIR         pipelines, shaders and meshes [default: data]
+  --output DIR       where exports are written [default: the data directory], headless
+                     results go there instead of stdout
+  --alpha ALPHA      tolerance overriding the one of the graph file
+  --pipeline NAME    pipeline overriding the one of the graph file
+  --log-level LEVEL  off, error, warn, info, debug or trace
+  --format FORMAT    headless results as text, csv or json [default: text]
+  --autosave         saves the graph file on exit if the window changed it
+  --help             prints this message";
+
+/// Command line of the binary.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Args {
+    pub headless: bool,
+    /// See [`Self::graph`].
+    pub graph: Option<PathBuf>,
+    pub data: PathBuf,
+    /// See [`Self::output`], headless results go to stdout without it.
+    pub output: Option<PathBuf>,
+    pub alpha: Option<f64>,
+    pub pipeline: Option<String>,
+    pub log_level: Option<LevelFilter>,
+    pub format: Format,
+    pub autosave: bool,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            headless: false,
+            graph: None,
+            data: "data".into(),
+            output: None,
+            alpha: None,
+            pipeline: None,
+            log_level: None,
+            format: Format::default(),
+            autosave: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ArgsError {
+    /// `--help` was passed, not an error as such.
+    Help,
+    MissingValue(String),
+    UnknownOption(String),
+    InvalidValue {
+        option: String,
+        value: String,
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Help => return write!(f, "{USAGE}"),
+            Self::MissingValue(option) => write!(f, "`{option}` expects a value"),
+            Self::UnknownOption(option) => write!(f, "unknown option `{option}`"),
+            Self::InvalidValue {
+                option,
+                value,
+                expected,
+            } => write!(
+                f,
+                "invalid value `{value}` for `{option}`, expected {expected}"
+            ),
+        }?;
+
+        write!(f, "\n\n{USAGE}")
+    }
+}
+
+impl std::error::Error for ArgsError {}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, ArgsError> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| ArgsError::MissingValue(arg.clone()))
+            };
+            let invalid = |value: String, expected| ArgsError::InvalidValue {
+                option: arg.clone(),
+                value,
+                expected,
+            };
+
+            match arg.as_str() {
+                "--help" | "-h" => return Err(ArgsError::Help),
+                "--headless" => parsed.headless = true,
+                "--autosave" => parsed.autosave = true,
+                "--data" => parsed.data = value()?.into(),
+                "--output" => parsed.output = Some(value()?.into()),
+                "--pipeline" => parsed.pipeline = Some(value()?),
+                "--alpha" => {
+                    let value = value()?;
+                    parsed.alpha = match value.parse::<f64>() {
+                        Ok(alpha) if alpha >= 0.0 && alpha.is_finite() => Some(alpha),
+                        _ => return Err(invalid(value, "a non-negative number")),
+                    };
+                }
+                "--log-level" => {
+                    let value = value()?;
+                    parsed.log_level = match LevelFilter::from_str(&value) {
+                        Ok(level) => Some(level),
+                        Err(_) => {
+                            return Err(invalid(value, "off, error, warn, info, debug or trace"));
+                        }
+                    };
+                }
+                "--format" => {
+                    let value = value()?;
+                    parsed.format = match value.as_str() {
+                        "text" => Format::Text,
+                        "csv" => Format::Csv,
+                        "json" => Format::Json,
+                        _ => return Err(invalid(value, "text, csv or json")),
+                    };
+                }
+                _ if arg.starts_with('-') => return Err(ArgsError::UnknownOption(arg)),
+                _ if parsed.graph.is_none() => parsed.graph = Some(arg.into()),
+                _ => return Err(ArgsError::UnknownOption(arg)),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    pub fn graph(&self) -> PathBuf {
+        self.graph
+            .clone()
+            .unwrap_or_else(|| self.data.join("graph_desc.toml"))
+    }
+
+    /// Directory of the exports of the window.
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn output(&self) -> &Path {
+        self.output.as_deref().unwrap_or(&self.data)
+    }
+
+    pub fn pipelines(&self) -> PathBuf {
+        self.data.join("pipelines.toml")
+    }
+
+    /// The graph file with the overrides of the command line.
+    pub fn load_graph(&self) -> Result<GraphDesc, LoadError> {
+        Ok(self.with_overrides(GraphDesc::load(&self.graph())?))
+    }
+
+    pub fn with_overrides(&self, mut desc: GraphDesc) -> GraphDesc {
+        if let Some(alpha) = self.alpha {
+            desc.alpha = alpha;
+        }
+        if let Some(pipeline) = self.pipeline.as_ref() {
+            desc.pipeline = Some(pipeline.clone());
+        }
+
+        desc
+    }
+}
+
+mod test {
+    #![allow(unused_imports)]
+    use std::path::{Path, PathBuf};
+
+    use log::LevelFilter;
+
+    use super::{Args, ArgsError};
+    use crate::headless::Format;
+
+    #[test]
+    fn args_test() {
+        let args = |args: &[&str]| Args::parse(args.iter().map(|arg| arg.to_string()));
+
+        let default = args(&[]).unwrap();
+        assert_eq!(default, Args::default());
+        assert_eq!(default.graph(), PathBuf::from("data/graph_desc.toml"));
+        assert_eq!(default.output(), Path::new("data"));
+
+        let parsed = args(&[
+            "--headless",
+            "grid.toml",
+            "--data",
+            "assets",
+            "--alpha",
+            "0.5",
+            "--format",
+            "csv",
+            "--log-level",
+            "info",
+            "--pipeline",
+            "random failures",
+        ])
+        .unwrap();
+        assert!(parsed.headless);
+        assert!(!parsed.autosave);
+        assert!(args(&["--autosave"]).unwrap().autosave);
+        assert_eq!(parsed.graph(), PathBuf::from("grid.toml"));
+        assert_eq!(parsed.pipelines(), PathBuf::from("assets/pipelines.toml"));
+        assert_eq!(parsed.output(), Path::new("assets"));
+        assert_eq!(parsed.alpha, Some(0.5));
+        assert_eq!(parsed.format, Format::Csv);
+        assert_eq!(parsed.log_level, Some(LevelFilter::Info));
+        assert_eq!(parsed.pipeline.as_deref(), Some("random failures"));
+
+        assert_eq!(args(&["--help"]), Err(ArgsError::Help));
+        assert_eq!(
+            args(&["--output"]),
+            Err(ArgsError::MissingValue("--output".to_owned()))
+        );
+        assert_eq!(
+            args(&["--window"]),
+            Err(ArgsError::UnknownOption("--window".to_owned()))
+        );
+        assert_eq!(
+            args(&["a.toml", "b.toml"]),
+            Err(ArgsError::UnknownOption("b.toml".to_owned()))
+        );
+        assert!(matches!(
+            args(&["--alpha", "-1"]),
+            Err(ArgsError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            args(&["--log-level", "loud"]),
+            Err(ArgsError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            args(&["--format", "xml"]),
+            Err(ArgsError::InvalidValue { .. })
+        ));
+    }
+}
@@ -0,0 +1,35 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::{Compute, ComputeError, stop::StopReason};
+
+/// Runs the computes of an [`super::ensemble::Ensemble`] or a [`super::sweep::Sweep`]
+/// one after the other, reporting every finished one to `progress` and giving up once
+/// `cancel` is set.
+pub(super) struct Batch<'a, P> {
+    cancel: &'a AtomicBool,
+    progress: P,
+    finished: usize,
+}
+
+impl<'a, P: FnMut(usize)> Batch<'a, P> {
+    pub(super) fn new(cancel: &'a AtomicBool, progress: P) -> Self {
+        Self {
+            cancel,
+            progress,
+            finished: 0,
+        }
+    }
+
+    /// Runs `compute` to its end, `None` if the batch was cancelled meanwhile.
+    pub(super) fn run(&mut self, mut compute: Compute) -> Result<Option<Compute>, ComputeError> {
+        if compute.run_with(self.cancel, |_, _| {})? == StopReason::Cancelled
+            || self.cancel.load(Ordering::Relaxed)
+        {
+            return Ok(None);
+        }
+
+        self.finished += 1;
+        (self.progress)(self.finished);
+        Ok(Some(compute))
+    }
+}
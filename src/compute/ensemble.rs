@@ -0,0 +1,220 @@
+use std::sync::atomic::AtomicBool;
+
+use crate::graph::GraphDesc;
+
+use super::{Compute, ComputeError, batch::Batch, state::State};
+
+/// Aggregate of one metric at one iteration over the runs.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub runs: usize,
+    pub mean: f64,
+    pub stddev: f64,
+    pub p5: f64,
+    pub median: f64,
+    pub p95: f64,
+}
+
+impl Stats {
+    /// Non-finite samples (e.g. an undefined beta) are skipped. Without any finite
+    /// sample all the values are NaN.
+    pub fn new(samples: &[f64]) -> Self {
+        let mut samples = samples
+            .iter()
+            .copied()
+            .filter(|sample| sample.is_finite())
+            .collect::<Vec<_>>();
+        if samples.is_empty() {
+            return Self {
+                runs: 0,
+                mean: f64::NAN,
+                stddev: f64::NAN,
+                p5: f64::NAN,
+                median: f64::NAN,
+                p95: f64::NAN,
+            };
+        }
+        samples.sort_by(f64::total_cmp);
+
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / n;
+        let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n;
+        let percentile = |p: f64| samples[((p * n).ceil() as usize).clamp(1, samples.len()) - 1];
+
+        Self {
+            runs: samples.len(),
+            mean,
+            stddev: variance.sqrt(),
+            p5: percentile(0.05),
+            median: percentile(0.5),
+            p95: percentile(0.95),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct EnsembleResult {
+    /// Seed of every run, in run order.
+    pub seeds: Vec<u64>,
+    /// Over the runs that computed the iteration, [`Stats::runs`] of them.
+    pub beta_deltas: Vec<Stats>,
+    pub ks: Vec<Stats>,
+    /// Over every run, a run that stopped early counting with its last iteration.
+    pub alive_fraction: Vec<Stats>,
+    pub giant_component: Vec<Stats>,
+}
+
+/// Runs the same pipeline on one graph several times with different attack seeds.
+/// Run `i` is seeded with `seed + i`, so an ensemble is reproduced from its first seed.
+pub struct Ensemble {
+    runs: usize,
+    seed: u64,
+}
+
+impl Ensemble {
+    pub fn new(runs: usize, seed: u64) -> Self {
+        Self { runs, seed }
+    }
+
+    pub fn runs(&self) -> usize {
+        self.runs
+    }
+
+    /// Runs every seed, building each run with `pipeline` from the reseeded graph.
+    pub fn run(
+        &self,
+        desc: &GraphDesc,
+        pipeline: impl Fn(&GraphDesc) -> Result<Compute, ComputeError>,
+    ) -> Result<EnsembleResult, ComputeError> {
+        self.run_with(desc, pipeline, &AtomicBool::new(false), |_| {})
+            .map(|result| result.expect("the ensemble is not cancelled"))
+    }
+
+    /// [`Self::run`] that drops the runs so far once `cancel` is set, returning `None`.
+    /// `progress` gets the number of seeds done out of [`Self::runs`].
+    pub fn run_with(
+        &self,
+        desc: &GraphDesc,
+        pipeline: impl Fn(&GraphDesc) -> Result<Compute, ComputeError>,
+        cancel: &AtomicBool,
+        progress: impl FnMut(usize),
+    ) -> Result<Option<EnsembleResult>, ComputeError> {
+        let mut batch = Batch::new(cancel, progress);
+        let mut seeds = vec![];
+        let mut beta_deltas = vec![];
+        let mut ks = vec![];
+        let mut alive_fraction = vec![];
+        let mut giant_component = vec![];
+
+        for run in 0..self.runs {
+            let seed = self.seed.wrapping_add(run as u64);
+            let mut desc = desc.clone();
+            desc.attack.seed = Some(seed);
+
+            let Some(compute) = batch.run(pipeline(&desc)?)? else {
+                return Ok(None);
+            };
+
+            let state = compute.state();
+            seeds.push(seed);
+            beta_deltas.push(state.beta_deltas.clone());
+            ks.push(state.ks.clone());
+            alive_fraction.push(Self::alive_fraction(state));
+            giant_component.push(state.giant_component.clone());
+        }
+
+        Ok(Some(EnsembleResult {
+            seeds,
+            beta_deltas: Self::aggregate(&beta_deltas),
+            ks: Self::aggregate(&ks),
+            alive_fraction: Self::aggregate(&Self::carry_forward(alive_fraction)),
+            giant_component: Self::aggregate(&Self::carry_forward(giant_component)),
+        }))
+    }
+
+    fn alive_fraction(state: &State) -> Vec<f64> {
+        (0..state.iter_count())
+            .map(|i| {
+                let graph = &state.at(i).graph;
+                graph.alive() as f64 / graph.node_count() as f64
+            })
+            .collect()
+    }
+
+    /// Pads every series to the longest one with its last value. The network of a run
+    /// that stopped early stays as it is, so it keeps counting at the later iterations
+    /// instead of leaving them to the runs that went on.
+    fn carry_forward(mut series: Vec<Vec<f64>>) -> Vec<Vec<f64>> {
+        let len = series.iter().map(Vec::len).max().unwrap_or(0);
+        for series in series.iter_mut() {
+            if let Some(&last) = series.last() {
+                series.resize(len, last);
+            }
+        }
+        series
+    }
+
+    /// Stats per index over the runs long enough to have it.
+    fn aggregate(series: &[Vec<f64>]) -> Vec<Stats> {
+        let len = series.iter().map(Vec::len).max().unwrap_or(0);
+        (0..len)
+            .map(|i| {
+                let samples = series
+                    .iter()
+                    .filter_map(|s| s.get(i).copied())
+                    .collect::<Vec<_>>();
+                Stats::new(&samples)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(unused_imports)]
+    use super::{Ensemble, Stats};
+    use crate::{
+        compute::pipeline::test::default_pipeline, config::AttackStrategy, graph::GraphDesc,
+    };
+    use std::sync::atomic::AtomicBool;
+
+    #[test]
+    fn stats_test() {
+        let stats = Stats::new(&[3.0, f64::NAN, 1.0, 2.0, 4.0]);
+        assert_eq!(stats.runs, 4);
+        assert_eq!(stats.mean, 2.5);
+        assert_eq!(stats.median, 2.0);
+        assert_eq!((stats.p5, stats.p95), (1.0, 4.0));
+        let empty = Stats::new(&[f64::NAN]);
+        assert_eq!(empty.runs, 0);
+        assert!(empty.mean.is_nan());
+    }
+
+    #[test]
+    fn ensemble_test() {
+        let mut desc = GraphDesc::example();
+        desc.attack.strategy = AttackStrategy::Random;
+
+        let pipeline = default_pipeline();
+        let pipeline = |desc: &GraphDesc| pipeline.compute(desc);
+
+        let first = Ensemble::new(8, 42).run(&desc, pipeline).unwrap();
+        let second = Ensemble::new(8, 42).run(&desc, pipeline).unwrap();
+
+        assert_eq!(first.seeds, (42..50).collect::<Vec<_>>());
+        assert_eq!(first.alive_fraction, second.alive_fraction);
+        assert_eq!(first.alive_fraction[0].runs, 8);
+        assert_eq!(first.alive_fraction[0].mean, 1.0);
+        // Runs of different lengths, the shorter ones keep counting with their end.
+        assert!(first.beta_deltas.last().unwrap().runs < 8);
+        assert!(first.alive_fraction.iter().all(|stats| stats.runs == 8));
+        assert!(first.giant_component.iter().all(|stats| stats.runs == 8));
+        // Every run has a k for each iteration, none of them for the initial one.
+        assert_eq!(first.ks.len(), first.alive_fraction.len());
+        assert_eq!(first.ks[0].runs, 0);
+
+        let cancelled =
+            Ensemble::new(8, 42).run_with(&desc, pipeline, &AtomicBool::new(true), |_| {});
+        assert!(cancelled.unwrap().is_none());
+    }
+}
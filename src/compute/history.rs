@@ -0,0 +1,134 @@
+use serde::Serialize;
+
+use crate::graph::node::Node;
+
+use super::state::{State, WaveKind};
+
+/// When and why a node was deleted.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct FailedAt {
+    /// Iteration the node was deleted while computing, it is dead from this one on.
+    pub iteration: usize,
+    pub wave: usize,
+    pub reason: WaveKind,
+}
+
+/// The deletion of every node of a run, next to the alive flags of the trackers which
+/// forget when a node died.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FailureHistory {
+    failed: Vec<Option<FailedAt>>,
+}
+
+#[derive(Serialize)]
+struct NodeRecord {
+    node: u32,
+    #[serde(flatten)]
+    failed_at: FailedAt,
+}
+
+#[derive(Serialize)]
+struct HistoryRecord {
+    nodes: Vec<NodeRecord>,
+}
+
+impl FailureHistory {
+    pub fn new(state: &State) -> Self {
+        let mut failed = vec![None; state.at(0).graph.node_count()];
+
+        for iteration in 1..state.iter_count() {
+            for (wave, w) in state.at(iteration).waves.iter().enumerate() {
+                for failure in w.failures.iter() {
+                    failed[failure.node.as_idx()] = Some(FailedAt {
+                        iteration,
+                        wave,
+                        reason: w.kind,
+                    });
+                }
+            }
+        }
+
+        Self { failed }
+    }
+
+    pub fn get(&self, node: Node) -> Option<FailedAt> {
+        self.failed[node.as_idx()]
+    }
+
+    /// Nodes deleted while computing `iteration` or an earlier one, in node order.
+    pub fn failed_by(&self, iteration: usize) -> impl Iterator<Item = (Node, FailedAt)> + '_ {
+        self.failed
+            .iter()
+            .enumerate()
+            .filter_map(move |(i, failed_at)| {
+                failed_at
+                    .filter(|failed_at| failed_at.iteration <= iteration)
+                    .map(|failed_at| (unsafe { Node::new(i as u32) }, failed_at))
+            })
+    }
+
+    pub fn to_toml(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string(&HistoryRecord {
+            nodes: self
+                .failed_by(usize::MAX)
+                .map(|(node, failed_at)| NodeRecord {
+                    node: node.as_idx() as u32,
+                    failed_at,
+                })
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(unused_imports)]
+    use super::FailureHistory;
+    use crate::{
+        compute::{pipeline::test::default_compute, state::WaveKind},
+        graph::GraphDesc,
+    };
+
+    #[test]
+    fn failure_history_test() {
+        let mut desc = GraphDesc::example();
+        desc.alpha = 0.2;
+
+        let mut compute = default_compute(&desc);
+        compute.run().unwrap();
+
+        let state = compute.state();
+        let history = state.failure_history();
+
+        for k in 0..state.iter_count() {
+            let tracker = &state.at(k).graph.tracker;
+            assert_eq!(
+                history.failed_by(k).count(),
+                tracker.node_count() - tracker.alive()
+            );
+
+            for (node, failed_at) in history.failed_by(k) {
+                assert!(!tracker.is_alive(node));
+                assert!(
+                    state
+                        .at(failed_at.iteration - 1)
+                        .graph
+                        .tracker
+                        .is_alive(node)
+                );
+            }
+        }
+
+        assert!(
+            history
+                .failed_by(usize::MAX)
+                .any(|(_, failed_at)| failed_at.reason == WaveKind::Attack)
+        );
+
+        let exported: toml::Table = toml::from_str(&history.to_toml().unwrap()).unwrap();
+        assert_eq!(
+            exported["nodes"].as_array().unwrap().len(),
+            history.failed_by(usize::MAX).count()
+        );
+    }
+}
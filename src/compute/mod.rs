@@ -1,33 +1,108 @@
+use std::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
 use state::{Iteration, State};
-use steps::ComputeStep;
+use steps::{ComputeStep, Flow, StepError};
+use stop::{StopCondition, StopReason};
 
-use crate::graph::GraphDesc;
+use crate::graph::{GraphDesc, validate::DescError};
 
+mod batch;
+pub mod ensemble;
+pub mod history;
+pub mod pipeline;
 pub mod state;
 pub mod steps;
+pub mod stop;
+pub mod sweep;
+pub mod tree;
+pub mod worker;
+
+/// Beta is only defined for components of more than two nodes.
+const MIN_ALIVE: usize = 3;
+
+#[derive(Debug, PartialEq)]
+pub enum ComputeError {
+    /// The graph description has problems, see [`GraphDesc::validate`].
+    InvalidGraph(Vec<DescError>),
+    InitialGraphTooSmall {
+        alive: usize,
+    },
+    Step {
+        step: &'static str,
+        reason: String,
+    },
+    /// The run panicked, e.g. on a bug in a step, with the panic message.
+    Panicked(String),
+}
+
+impl fmt::Display for ComputeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidGraph(errors) => {
+                write!(f, "invalid graph:")?;
+                errors.iter().try_for_each(|error| write!(f, "\n  {error}"))
+            }
+            Self::InitialGraphTooSmall { alive } => write!(
+                f,
+                "initial graph has {alive} alive nodes, at least {MIN_ALIVE} are required"
+            ),
+            Self::Step { step, reason } => write!(f, "step `{step}` failed: {reason}"),
+            Self::Panicked(message) => write!(f, "the run panicked: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ComputeError {}
 
-#[derive(Default)]
 pub struct Compute {
     state: State,
     steps: Vec<Box<dyn ComputeStep>>,
+    stop_conditions: Vec<StopCondition>,
 }
 
-impl Compute {
-    pub fn new(desc: GraphDesc) -> Self {
-        let mut state = State::new(desc.alpha);
-        state.add_iter(Iteration::new(desc));
-
+impl Default for Compute {
+    fn default() -> Self {
         Self {
-            state,
+            state: Default::default(),
             steps: vec![],
+            stop_conditions: vec![
+                StopCondition::MinAlive(MIN_ALIVE),
+                StopCondition::SteadyState,
+            ],
         }
     }
+}
+
+impl Compute {
+    pub fn new(desc: GraphDesc) -> Result<Self, ComputeError> {
+        Ok(Self {
+            state: State::initial(desc).map_err(ComputeError::InvalidGraph)?,
+            ..Default::default()
+        })
+    }
 
     pub fn add_step<T: ComputeStep + 'static>(mut self, step: T) -> Self {
         self.steps.push(Box::new(step));
         self
     }
 
+    /// Adds a condition that ends [`Self::run`]. The run stops at the first condition
+    /// met. By default it stops when fewer than three nodes are alive or when a pass
+    /// deletes nothing, as every further pass would repeat it.
+    pub fn stop_when(mut self, condition: StopCondition) -> Self {
+        self.stop_conditions.push(condition);
+        self
+    }
+
+    /// Replaces the stop conditions, the defaults included, see [`Self::stop_when`].
+    pub fn stop_only_when(mut self, conditions: impl IntoIterator<Item = StopCondition>) -> Self {
+        self.stop_conditions = conditions.into_iter().collect();
+        self
+    }
+
     pub fn state(&self) -> &State {
         &self.state
     }
@@ -36,37 +111,128 @@ impl Compute {
         &mut self.state
     }
 
-    pub fn run(&mut self) {
-        'outer: loop {
-            let Iteration { graph, .. } = self.state.get_mut();
-            if graph.alive() == 2 {
-                // TODO: kostil
-                assert!(self.state.iter_count() > 1, "Initial graph is invalid");
-                self.state.pop();
-                break 'outer;
+    /// Runs the steps until a stop condition is met, a step asks to stop or a step fails.
+    /// Conditions are checked before every pass, the first one included, against the
+    /// iteration the last pass started: it is
+    /// discarded if no node was deleted, otherwise its metrics are computed before
+    /// stopping. On failure the iterations computed so far are kept.
+    pub fn run(&mut self) -> Result<StopReason, ComputeError> {
+        self.run_with(&AtomicBool::new(false), |_, _| {})
+    }
+
+    /// [`Self::run`] that stops when `cancel` is set, discarding the iteration being
+    /// computed. `progress` is called after every pass with the number of iterations
+    /// whose metrics are computed.
+    pub fn run_with(
+        &mut self,
+        cancel: &AtomicBool,
+        mut progress: impl FnMut(&State, usize),
+    ) -> Result<StopReason, ComputeError> {
+        let alive = self.state.get().graph.alive();
+        if alive < MIN_ALIVE {
+            return Err(ComputeError::InitialGraphTooSmall { alive });
+        }
+
+        let reason = loop {
+            if let Some(&condition) = self
+                .stop_conditions
+                .iter()
+                .find(|condition| condition.is_met(&self.state))
+            {
+                self.settle()?;
+                break StopReason::Condition(condition);
+            }
+
+            if let Some((step, reason, started)) = self.pass()? {
+                if started {
+                    self.settle()?;
+                }
+                break StopReason::Step { step, reason };
             }
 
-            for step in self.steps.iter_mut() {
-                if !step.compute(&mut self.state) {
-                    break 'outer;
+            if cancel.load(Ordering::Relaxed) {
+                if self.state.iter_count() > 1 {
+                    self.state.pop();
                 }
+                break StopReason::Cancelled;
+            }
+
+            progress(&self.state, self.state.iter_count() - 1);
+        };
+
+        self.state.set_current_iter(0);
+        Ok(reason)
+    }
+
+    /// Runs every step once. Returns the step that asked to stop, its reason and whether
+    /// a new iteration was started before it.
+    fn pass(&mut self) -> Result<Option<(&'static str, String, bool)>, ComputeError> {
+        let mut started = false;
+        for step in self.steps.iter_mut() {
+            match step.compute(&mut self.state) {
+                Ok(Flow::Continue) => {}
+                Ok(Flow::Stop(reason)) => return Ok(Some((step.name(), reason, started))),
+                Err(StepError { reason }) => {
+                    return Err(ComputeError::Step {
+                        step: step.name(),
+                        reason,
+                    });
+                }
+            }
+            started |= step.starts_iteration();
+        }
+
+        Ok(None)
+    }
+
+    /// Ends the run on the iteration the last pass started: it is discarded if no node
+    /// was deleted, otherwise its metrics are computed.
+    fn settle(&mut self) -> Result<(), ComputeError> {
+        if StopCondition::is_steady(&self.state) {
+            self.state.pop();
+            return Ok(());
+        }
+
+        for step in self.steps.iter_mut() {
+            if step.starts_iteration() {
+                break;
+            }
+
+            // The run ends anyway, a step asking to stop changes nothing.
+            if let Err(StepError { reason }) = step.compute(&mut self.state) {
+                return Err(ComputeError::Step {
+                    step: step.name(),
+                    reason,
+                });
             }
         }
+
+        Ok(())
     }
 }
 
+/// Starts a new iteration from the current one. The shortest paths are moved rather
+/// than cloned, so only the iteration being computed holds the O(n²) distance tables.
 pub struct CopyIteration;
 
 impl ComputeStep for CopyIteration {
-    fn compute(&mut self, state: &mut State) -> bool {
-        let Iteration { graph, info } = state.get();
+    fn name(&self) -> &'static str {
+        "copy_iteration"
+    }
+
+    fn compute(&mut self, state: &mut State) -> Result<Flow, StepError> {
+        let Iteration { graph, info, .. } = state.get_mut();
 
-        let graph = graph.clone();
+        let graph = graph.split_off();
         let info = info.clone();
 
         state.add_iter((graph, info).into());
-        state.next();
+        state.next_iter();
 
+        Ok(Flow::Continue)
+    }
+
+    fn starts_iteration(&self) -> bool {
         true
     }
 }
@@ -74,7 +240,167 @@ impl ComputeStep for CopyIteration {
 pub struct UpdatePaths;
 
 impl ComputeStep for UpdatePaths {
-    fn compute(&mut self, state: &mut State) -> bool {
-        state.get_mut().graph.update_paths().is_some()
+    fn name(&self) -> &'static str {
+        "update_paths"
+    }
+
+    fn compute(&mut self, state: &mut State) -> Result<Flow, StepError> {
+        state.get_mut().graph.update_paths();
+        Ok(Flow::Continue)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(unused_imports)]
+    use super::{Compute, ComputeError};
+    use crate::{
+        compute::{
+            pipeline::test::default_compute,
+            stop::{StopCondition, StopReason},
+        },
+        config::{AttackDesc, AttackStrategy, CapacityMode},
+        graph::{GraphDesc, NodeDesc, validate::DescError},
+    };
+    use std::sync::atomic::AtomicBool;
+
+    #[test]
+    fn stop_conditions_test() {
+        let mut max_iterations =
+            default_compute(&GraphDesc::example()).stop_when(StopCondition::MaxIterations(3));
+        let reason = max_iterations.run().unwrap();
+        assert_eq!(
+            reason,
+            StopReason::Condition(StopCondition::MaxIterations(3))
+        );
+        assert_eq!(max_iterations.state().iter_count(), 3);
+        assert_eq!(max_iterations.state().giant_component.len(), 3);
+
+        let mut giant = default_compute(&GraphDesc::example())
+            .stop_when(StopCondition::GiantComponentBelow(0.5));
+        giant.run().unwrap();
+        let g = giant.state().giant_component.last().copied().unwrap();
+        assert!(g < 0.5);
+        assert!(
+            giant.state().giant_component[..giant.state().iter_count() - 1]
+                .iter()
+                .all(|&g| g >= 0.5)
+        );
+
+        let too_small = GraphDesc {
+            nodes: (0..2)
+                .map(|i| NodeDesc {
+                    node_id: i,
+                    nodes: if i == 0 { vec![1.into()] } else { vec![] },
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        assert_eq!(
+            default_compute(&too_small).run(),
+            Err(ComputeError::InitialGraphTooSmall { alive: 2 })
+        );
+
+        let mut dangling = too_small;
+        dangling.nodes[1].nodes.push(7.into());
+        assert_eq!(
+            Compute::new(dangling).err(),
+            Some(ComputeError::InvalidGraph(vec![
+                DescError::DanglingNeighbour {
+                    node: 1,
+                    neighbour: 7
+                }
+            ]))
+        );
+    }
+
+    #[test]
+    fn cancel_test() {
+        // Without tolerance the first attack overloads some nodes.
+        let mut desc = GraphDesc::example();
+        desc.alpha = 0.0;
+
+        let mut compute = default_compute(&desc);
+        let reason = compute.run_with(&AtomicBool::new(true), |_, _| {});
+        assert_eq!(reason, Ok(StopReason::Cancelled));
+
+        // The discarded iteration takes its k along, the initial one has none.
+        let state = compute.state();
+        assert_eq!(state.iter_count(), 1);
+        assert_eq!(state.beta_deltas.len(), 1);
+        assert_eq!(state.giant_component.len(), 1);
+        assert_eq!(state.relative_efficiency.len(), 1);
+        assert!(state.ks.len() <= 1 && state.ks.iter().all(|k| k.is_nan()));
+    }
+
+    #[test]
+    fn step_results_test() {
+        // Huge tolerance: nothing is overloaded, only the two targets are removed.
+        let mut desc = GraphDesc::example();
+        desc.alpha = 1000.0;
+        desc.attack = AttackDesc {
+            strategy: AttackStrategy::Targets,
+            targets: vec![9, 3],
+            ..Default::default()
+        };
+
+        let mut targets = default_compute(&desc);
+        match targets.run() {
+            Ok(StopReason::Step { step, .. }) => assert_eq!(step, "attack"),
+            other => panic!("unexpected result {other:?}"),
+        }
+        assert_eq!(targets.state().iter_count(), 3);
+        assert_eq!(targets.state().beta_deltas.len(), 3);
+
+        // A negative alpha is rejected up front, or by the step if it is set later on.
+        let mut negative = default_compute(&desc);
+        desc.alpha = -1.0;
+        assert_eq!(
+            Compute::new(desc).err(),
+            Some(ComputeError::InvalidGraph(vec![DescError::InvalidAlpha(
+                -1.0
+            )]))
+        );
+        negative.state_mut().alpha = -1.0;
+        assert!(matches!(
+            negative.run(),
+            Err(ComputeError::Step {
+                step: "capacity",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn capacity_mode_test() {
+        let mut desc = GraphDesc::example();
+        desc.alpha = 0.2;
+
+        let mut frozen = default_compute(&desc);
+        frozen.run().unwrap();
+        let state = frozen.state();
+        assert!(state.iter_count() > 2);
+        let initial = &state.at(0).info.capacity;
+        for k in 1..state.iter_count() {
+            let iteration = state.at(k);
+            for i in iteration.graph.tracker.iter_alive() {
+                assert_eq!(iteration.info.capacity[i], initial[i]);
+            }
+        }
+
+        desc.capacity = CapacityMode::Adaptive;
+        let mut adaptive = default_compute(&desc);
+        adaptive.run().unwrap();
+        let state = adaptive.state();
+        let initial = &state.at(0).info.capacity;
+        let iteration = state.at(1);
+        assert!(
+            iteration
+                .graph
+                .tracker
+                .iter_alive()
+                .any(|i| iteration.info.capacity[i] != initial[i])
+        );
     }
 }
@@ -0,0 +1,528 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    path::Path,
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{AttackStrategy, BetweennessMode, CapacityMode, Ranking},
+    graph::GraphDesc,
+};
+
+use super::{
+    Compute, ComputeError, CopyIteration, UpdatePaths,
+    steps::{
+        ComputeStep, attack::Attack, betweeness::Betweeness, capacity::Capacity, cascade::Cascade,
+        components::ConnectedComponents, delete::DeleteOverloaded, efficiency::Efficiency,
+        zmax::Zmax,
+    },
+    stop::{StopCondition, StopDesc},
+};
+
+/// One step of a pipeline: its registered name and parameters.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StepDesc {
+    pub step: String,
+    #[serde(flatten)]
+    pub params: toml::Table,
+}
+
+impl StepDesc {
+    pub(super) fn new(step: &str) -> Self {
+        Self {
+            step: step.to_owned(),
+            params: Default::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PipelineDesc {
+    pub name: String,
+    pub steps: Vec<StepDesc>,
+    /// Replaces the default stop conditions of the runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop: Option<StopDesc>,
+}
+
+impl Default for PipelineDesc {
+    /// Metrics of the current iteration, then an attack on a copy of it followed by the
+    /// nodes it overloads.
+    fn default() -> Self {
+        Self {
+            name: "default".to_owned(),
+            steps: [
+                "update_paths",
+                "connected_components",
+                "zmax",
+                "efficiency",
+                "betweenness",
+                "capacity",
+                "copy_iteration",
+                "attack",
+                "update_paths",
+                "betweenness",
+                "delete_overloaded",
+            ]
+            .map(StepDesc::new)
+            .to_vec(),
+            stop: None,
+        }
+    }
+}
+
+/// Contents of a pipelines file.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PipelinesDesc {
+    #[serde(default)]
+    pub pipelines: Vec<PipelineDesc>,
+}
+
+#[derive(Debug)]
+pub enum PipelineError {
+    UnknownStep {
+        index: usize,
+        step: String,
+    },
+    InvalidParams {
+        index: usize,
+        step: String,
+        error: toml::de::Error,
+    },
+    /// Without `copy_iteration` the pipeline would loop on the first iteration.
+    NoIterationStep,
+    /// The `stop` table sets no condition or an invalid one.
+    InvalidStop(String),
+    /// The step reads the shortest paths while they miss a deletion, either before the
+    /// first `update_paths` or on the way around from a deleting step.
+    StalePaths {
+        index: usize,
+        step: String,
+    },
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownStep { index, step } => write!(f, "step {index}: unknown step `{step}`"),
+            Self::InvalidParams { index, step, error } => {
+                write!(
+                    f,
+                    "step {index}: invalid parameters of `{step}`: {}",
+                    error.message()
+                )
+            }
+            Self::NoIterationStep => write!(f, "the pipeline has no `copy_iteration` step"),
+            Self::InvalidStop(reason) => write!(f, "invalid `stop` table: {reason}"),
+            Self::StalePaths { index, step } => write!(
+                f,
+                "step {index}: `{step}` reads the shortest paths before `update_paths` brings them up to date"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PipelineError {}
+
+type StepFactory = Arc<dyn Fn(&GraphDesc) -> Box<dyn ComputeStep> + Send + Sync>;
+type StepConstructor = fn(toml::Table) -> Result<StepFactory, toml::de::Error>;
+
+/// Parameters of a step without any.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NoParams {}
+
+/// Overrides of the graph's betweenness mode.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BetweennessParams {
+    mode: Option<BetweennessMode>,
+}
+
+/// Overrides of the graph's capacity mode.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CapacityParams {
+    mode: Option<CapacityMode>,
+}
+
+/// Overrides of the graph's attack. The seed is left to the graph, so ensembles can
+/// still reseed every run.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AttackParams {
+    strategy: Option<AttackStrategy>,
+    ranking: Option<Ranking>,
+    targets: Option<Vec<u32>>,
+}
+
+/// What a step does to the shortest paths of the graph, checked by [`Registry::validate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathUse {
+    Ignores,
+    Reads,
+    /// Leaves them up to date, whatever it does in between.
+    Updates,
+    /// Deletes nodes, so the paths are stale afterwards.
+    Deletes,
+}
+
+/// Maps step names to constructors of [`ComputeStep`]s.
+pub struct Registry {
+    constructors: HashMap<&'static str, (PathUse, StepConstructor)>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Self {
+            constructors: HashMap::new(),
+        };
+
+        registry.register("update_paths", PathUse::Updates, |params| {
+            params.try_into::<NoParams>()?;
+            Ok(Arc::new(|_| Box::new(UpdatePaths)))
+        });
+        registry.register("copy_iteration", PathUse::Ignores, |params| {
+            params.try_into::<NoParams>()?;
+            Ok(Arc::new(|_| Box::new(CopyIteration)))
+        });
+        registry.register("connected_components", PathUse::Ignores, |params| {
+            params.try_into::<NoParams>()?;
+            Ok(Arc::new(|_| Box::new(ConnectedComponents)))
+        });
+        registry.register("zmax", PathUse::Reads, |params| {
+            params.try_into::<NoParams>()?;
+            Ok(Arc::new(|_| Box::new(Zmax)))
+        });
+        registry.register("efficiency", PathUse::Reads, |params| {
+            params.try_into::<NoParams>()?;
+            Ok(Arc::new(|_| Box::new(Efficiency)))
+        });
+        registry.register("capacity", PathUse::Ignores, |params| {
+            let CapacityParams { mode } = params.try_into()?;
+            Ok(Arc::new(move |desc| {
+                Box::new(Capacity::new(mode.unwrap_or(desc.capacity)))
+            }))
+        });
+        registry.register("delete_overloaded", PathUse::Deletes, |params| {
+            params.try_into::<NoParams>()?;
+            Ok(Arc::new(|_| Box::new(DeleteOverloaded)))
+        });
+        registry.register("betweenness", PathUse::Reads, |params| {
+            let BetweennessParams { mode } = params.try_into()?;
+            Ok(Arc::new(move |desc| {
+                Box::new(Betweeness::new(mode.unwrap_or(desc.betweenness)))
+            }))
+        });
+        registry.register("cascade", PathUse::Updates, |params| {
+            let BetweennessParams { mode } = params.try_into()?;
+            Ok(Arc::new(move |desc| {
+                Box::new(Cascade::new(mode.unwrap_or(desc.betweenness)))
+            }))
+        });
+        // Closeness brings the paths it ranks by up to date itself.
+        registry.register("attack", PathUse::Deletes, |params| {
+            let AttackParams {
+                strategy,
+                ranking,
+                targets,
+            } = params.try_into()?;
+            Ok(Arc::new(move |desc| {
+                let mut attack = desc.attack.clone();
+                attack.strategy = strategy.unwrap_or(attack.strategy);
+                attack.ranking = ranking.unwrap_or(attack.ranking);
+                attack.targets = targets.clone().unwrap_or(attack.targets);
+                Box::new(Attack::new(attack))
+            }))
+        });
+
+        registry
+    }
+}
+
+impl Registry {
+    pub fn register(&mut self, name: &'static str, paths: PathUse, constructor: StepConstructor) {
+        self.constructors.insert(name, (paths, constructor));
+    }
+
+    /// Checks every step of `desc`, reporting all the problems found.
+    pub fn validate(&self, desc: &PipelineDesc) -> Result<Pipeline, Vec<PipelineError>> {
+        let mut steps = vec![];
+        let mut errors = vec![];
+
+        for (index, StepDesc { step, params }) in desc.steps.iter().enumerate() {
+            let Some((_, constructor)) = self.constructors.get(step.as_str()) else {
+                errors.push(PipelineError::UnknownStep {
+                    index,
+                    step: step.clone(),
+                });
+                continue;
+            };
+
+            match constructor(params.clone()) {
+                Ok(factory) => steps.push(factory),
+                Err(error) => errors.push(PipelineError::InvalidParams {
+                    index,
+                    step: step.clone(),
+                    error,
+                }),
+            }
+        }
+
+        if !desc.steps.iter().any(|step| step.step == "copy_iteration") {
+            errors.push(PipelineError::NoIterationStep);
+        }
+
+        let stop = match desc.stop.as_ref().map(StopDesc::conditions).transpose() {
+            Ok(stop) => stop,
+            Err(reason) => {
+                errors.push(PipelineError::InvalidStop(reason));
+                None
+            }
+        };
+
+        errors.extend(self.stale_reads(desc));
+
+        if errors.is_empty() {
+            Ok(Pipeline {
+                name: desc.name.clone(),
+                steps,
+                stop,
+            })
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl Registry {
+    /// Steps reading stale paths. Passes run the steps in a loop and the paths start
+    /// out stale, so two rounds see every state a step can find the paths in.
+    fn stale_reads(&self, desc: &PipelineDesc) -> Vec<PipelineError> {
+        let mut stale = BTreeMap::new();
+        let mut fresh = false;
+
+        for _ in 0..2 {
+            for (index, StepDesc { step, .. }) in desc.steps.iter().enumerate() {
+                match self.constructors.get(step.as_str()) {
+                    Some((PathUse::Reads, _)) if !fresh => {
+                        stale.entry(index).or_insert_with(|| step.clone());
+                    }
+                    Some((PathUse::Updates, _)) => fresh = true,
+                    Some((PathUse::Deletes, _)) => fresh = false,
+                    _ => {}
+                }
+            }
+        }
+
+        stale
+            .into_iter()
+            .map(|(index, step)| PipelineError::StalePaths { index, step })
+            .collect()
+    }
+}
+
+/// A validated pipeline, building a fresh [`Compute`] for every run. Clones share the
+/// steps' constructors, so a pipeline is cheap to send to a worker thread.
+#[derive(Clone)]
+pub struct Pipeline {
+    name: String,
+    steps: Vec<StepFactory>,
+    /// `None` keeps the default stop conditions.
+    stop: Option<Vec<StopCondition>>,
+}
+
+impl Pipeline {
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn compute(&self, desc: &GraphDesc) -> Result<Compute, ComputeError> {
+        let mut compute = Compute::new(desc.clone())?;
+        compute.steps = self.steps.iter().map(|factory| factory(desc)).collect();
+        if let Some(stop) = self.stop.as_ref() {
+            compute = compute.stop_only_when(stop.iter().copied());
+        }
+        Ok(compute)
+    }
+}
+
+/// The built-in pipeline followed by the valid pipelines of the file at `path`, with
+/// the problems found. A missing file only leaves the built-in pipeline.
+pub fn load(path: &Path) -> (Vec<Pipeline>, Vec<String>) {
+    let registry = Registry::default();
+    let mut pipelines = vec![registry.validate(&PipelineDesc::default()).unwrap()];
+    let mut errors = vec![];
+    let display = path.display();
+
+    let desc = match std::fs::read_to_string(path) {
+        Ok(desc) => desc,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return (pipelines, errors),
+        Err(err) => {
+            errors.push(format!("Failed to read {display}: {err}"));
+            return (pipelines, errors);
+        }
+    };
+
+    match toml::from_str::<PipelinesDesc>(desc.as_str()) {
+        Ok(desc) => {
+            for pipeline in desc.pipelines.iter() {
+                match registry.validate(pipeline) {
+                    Ok(pipeline) => pipelines.push(pipeline),
+                    Err(pipeline_errors) => errors.extend(
+                        pipeline_errors
+                            .iter()
+                            .map(|err| format!("Pipeline `{}`, {err}", pipeline.name)),
+                    ),
+                }
+            }
+        }
+        Err(err) => errors.push(format!("Failed to parse {display}: {err}")),
+    }
+
+    (pipelines, errors)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    #![allow(unused_imports)]
+    use super::{Pipeline, PipelineDesc, PipelineError, PipelinesDesc, Registry};
+    use crate::{
+        compute::{
+            Compute,
+            stop::{StopCondition, StopReason},
+        },
+        graph::GraphDesc,
+    };
+
+    /// The built-in pipeline, shared by the tests of the other modules.
+    pub(crate) fn default_pipeline() -> Pipeline {
+        Registry::default()
+            .validate(&PipelineDesc::default())
+            .unwrap()
+    }
+
+    /// A run of the built-in pipeline on a valid graph.
+    pub(crate) fn default_compute(desc: &GraphDesc) -> Compute {
+        default_pipeline().compute(desc).unwrap()
+    }
+
+    /// The pipeline of `data/pipelines.toml` called `name`.
+    pub(crate) fn shipped(name: &str) -> Pipeline {
+        let desc: PipelinesDesc =
+            toml::from_str(include_str!("../../data/pipelines.toml")).unwrap();
+        let pipeline = desc.pipelines.iter().find(|p| p.name == name).unwrap();
+        Registry::default().validate(pipeline).unwrap()
+    }
+
+    #[test]
+    fn pipeline_test() {
+        let registry = Registry::default();
+
+        let default = registry.validate(&PipelineDesc::default()).unwrap();
+        let mut compute = default.compute(&GraphDesc::example()).unwrap();
+        assert!(compute.run().is_ok());
+        assert!(compute.state().iter_count() > 1);
+
+        let desc: PipelinesDesc = toml::from_str(
+            r#"
+            [[pipelines]]
+            name = "random"
+            steps = [
+                { step = "update_paths" },
+                { step = "betweenness", mode = "legacy" },
+                { step = "copy_iteration" },
+                { step = "attack", strategy = "random" },
+            ]
+            stop = { max_iterations = 4 }
+
+            [[pipelines]]
+            name = "broken"
+            steps = [
+                { step = "update_path" },
+                { step = "attack", strategy = "loudest" },
+                { step = "zmax", alpha = 1.0 },
+            ]
+            stop = {}
+            "#,
+        )
+        .unwrap();
+
+        // The stop table replaces the defaults, so the run does not stop at 3 alive nodes.
+        let random = registry.validate(&desc.pipelines[0]).unwrap();
+        let mut compute = random.compute(&GraphDesc::example()).unwrap();
+        assert_eq!(
+            compute.run(),
+            Ok(StopReason::Condition(StopCondition::MaxIterations(4)))
+        );
+        assert_eq!(compute.state().iter_count(), 4);
+
+        let errors = registry.validate(&desc.pipelines[1]).err().unwrap();
+        assert_eq!(errors.len(), 6);
+        assert!(
+            matches!(&errors[0], PipelineError::UnknownStep { index: 0, step } if step == "update_path")
+        );
+        assert!(matches!(
+            &errors[1],
+            PipelineError::InvalidParams { index: 1, .. }
+        ));
+        assert!(matches!(
+            &errors[2],
+            PipelineError::InvalidParams { index: 2, .. }
+        ));
+        assert!(matches!(&errors[3], PipelineError::NoIterationStep));
+        assert!(matches!(&errors[4], PipelineError::InvalidStop(_)));
+        assert!(matches!(
+            &errors[5],
+            PipelineError::StalePaths { index: 2, .. }
+        ));
+
+        let no_iteration = PipelineDesc {
+            name: "no iteration".to_owned(),
+            steps: vec![super::StepDesc::new("update_paths")],
+            stop: None,
+        };
+        let errors = registry.validate(&no_iteration).err().unwrap();
+        assert!(matches!(errors[..], [PipelineError::NoIterationStep]));
+
+        // The zmax of every pass before the first update and the betweenness after the
+        // attack read stale paths, the efficiency runs after the update.
+        let stale = PipelineDesc {
+            name: "stale".to_owned(),
+            steps: [
+                "zmax",
+                "update_paths",
+                "efficiency",
+                "copy_iteration",
+                "attack",
+                "betweenness",
+            ]
+            .map(super::StepDesc::new)
+            .to_vec(),
+            stop: None,
+        };
+        let errors = registry.validate(&stale).err().unwrap();
+        assert_eq!(errors.len(), 2);
+        assert!(
+            matches!(&errors[0], PipelineError::StalePaths { index: 0, step } if step == "zmax")
+        );
+        assert!(matches!(
+            &errors[1],
+            PipelineError::StalePaths { index: 5, .. }
+        ));
+    }
+
+    #[test]
+    fn shipped_pipelines_test() {
+        let registry = Registry::default();
+        let desc: PipelinesDesc =
+            toml::from_str(include_str!("../../data/pipelines.toml")).unwrap();
+
+        for pipeline in desc.pipelines.iter() {
+            assert!(registry.validate(pipeline).is_ok(), "{}", pipeline.name);
+        }
+    }
+}
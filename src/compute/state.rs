@@ -1,31 +1,103 @@
-use crate::graph::{Graph, GraphDesc, GraphInfo};
+use std::fmt;
+
+use serde::Serialize;
+
+use super::{history::FailureHistory, tree::CascadeTree};
+
+use crate::graph::{
+    Graph, GraphDesc, GraphInfo,
+    node::{Node, NodeStatusTracker},
+    validate::DescError,
+};
+
+/// What deleted the nodes of a [`Wave`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WaveKind {
+    Attack,
+    Overload,
+}
+
+impl fmt::Display for WaveKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Attack => write!(f, "attack"),
+            Self::Overload => write!(f, "overload"),
+        }
+    }
+}
+
+/// A deleted node with its load and capacity when it was deleted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Failure {
+    pub node: Node,
+    pub load: f64,
+    pub capacity: f64,
+}
+
+/// Nodes deleted together while computing an iteration: the attacked node, or the
+/// nodes one recompute of the load found overloaded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Wave {
+    pub kind: WaveKind,
+    pub failures: Vec<Failure>,
+}
 
 #[derive(Clone, Debug)]
 pub struct Iteration {
     pub graph: Graph,
     pub info: GraphInfo,
+    /// Deletions that produced this iteration from the previous one, in order.
+    pub waves: Vec<Wave>,
 }
 
 impl Iteration {
-    pub(super) fn new(desc: GraphDesc) -> Self {
-        let graph = Graph::from(desc);
+    pub(super) fn new(desc: GraphDesc) -> Result<Self, Vec<DescError>> {
+        let graph = Graph::try_from(desc)?;
         let info = GraphInfo::new(&graph.tracker);
 
-        Self { graph, info }
+        Ok(Self {
+            graph,
+            info,
+            waves: vec![],
+        })
     }
 }
 
 impl From<(Graph, GraphInfo)> for Iteration {
     fn from((graph, info): (Graph, GraphInfo)) -> Self {
-        Self { graph, info }
+        Self {
+            graph,
+            info,
+            waves: vec![],
+        }
     }
 }
 
+/// Iterations finished since the last update together with every metric so far, used
+/// to mirror a [`State`] computed on another thread.
+#[derive(Debug)]
+pub struct StateUpdate {
+    start: usize,
+    iterations: Vec<Iteration>,
+    ks: Vec<f64>,
+    beta_deltas: Vec<f64>,
+    giant_component: Vec<f64>,
+    relative_efficiency: Vec<f64>,
+}
+
 #[derive(Debug, Default)]
 pub struct State {
     pub alpha: f64,
+    /// k = 1 / (nodes deleted by overloads) of every iteration, NaN for the initial
+    /// iteration and the ones where nothing was overloaded, so `ks[i]` belongs to
+    /// iteration `i` in every run.
     pub ks: Vec<f64>,
     pub beta_deltas: Vec<f64>,
+    /// Relative size of the largest connected component, G = N' / N.
+    pub giant_component: Vec<f64>,
+    /// Global efficiency relative to the first iteration, E / E₀.
+    pub relative_efficiency: Vec<f64>,
     iterations: Vec<Iteration>,
     current_iter: isize,
 }
@@ -38,6 +110,35 @@ impl State {
         }
     }
 
+    /// A state holding only the initial iteration of `desc`, its metrics not computed.
+    pub fn initial(desc: GraphDesc) -> Result<Self, Vec<DescError>> {
+        let mut state = Self::new(desc.alpha);
+        state.add_iter(Iteration::new(desc)?);
+        Ok(state)
+    }
+
+    /// Iterations `start..end` and the current metrics.
+    pub(super) fn update(&self, start: usize, end: usize) -> StateUpdate {
+        StateUpdate {
+            start,
+            iterations: self.iterations[start..end].to_vec(),
+            ks: self.ks.clone(),
+            beta_deltas: self.beta_deltas.clone(),
+            giant_component: self.giant_component.clone(),
+            relative_efficiency: self.relative_efficiency.clone(),
+        }
+    }
+
+    /// Replaces the iterations from the update on and all the metrics.
+    pub fn apply(&mut self, update: StateUpdate) {
+        self.iterations.truncate(update.start);
+        self.iterations.extend(update.iterations);
+        self.ks = update.ks;
+        self.beta_deltas = update.beta_deltas;
+        self.giant_component = update.giant_component;
+        self.relative_efficiency = update.relative_efficiency;
+    }
+
     pub(super) fn add_iter(&mut self, iter: Iteration) {
         self.iterations.push(iter);
     }
@@ -50,9 +151,37 @@ impl State {
         &mut self.iterations[self.current_iter as usize]
     }
 
+    /// Discards the last iteration together with its entries of the metrics.
     pub(super) fn pop(&mut self) {
         self.iterations.pop();
         self.current_iter = 0;
+
+        let count = self.iter_count();
+        self.beta_deltas.truncate(count);
+        self.giant_component.truncate(count);
+        self.relative_efficiency.truncate(count);
+        self.ks.truncate(count);
+    }
+
+    /// Sets the k of the current iteration from its overload waves so far, NaN if
+    /// nothing was overloaded. Iterations before it without a k get NaN, the initial
+    /// one included, so `ks[i]` is the k of iteration `i` in every run.
+    pub(super) fn record_k(&mut self) {
+        let deleted = self
+            .get()
+            .waves
+            .iter()
+            .filter(|wave| wave.kind == WaveKind::Overload)
+            .map(|wave| wave.failures.len())
+            .sum::<usize>();
+        let k = if deleted > 0 {
+            1.0 / deleted as f64
+        } else {
+            f64::NAN
+        };
+
+        self.ks.resize(self.current_iter(), f64::NAN);
+        self.ks.push(k);
     }
 
     pub fn iter_count(&self) -> usize {
@@ -64,6 +193,35 @@ impl State {
         &self.iterations[idx]
     }
 
+    /// Iteration `idx` part way through: the graph it was computed from and the nodes
+    /// still alive after its first `waves` waves.
+    pub fn after_waves(&self, idx: usize, waves: usize) -> (&Graph, NodeStatusTracker) {
+        if idx == 0 {
+            let graph = &self.at(0).graph;
+            return (graph, graph.tracker.clone());
+        }
+
+        let graph = &self.at(idx - 1).graph;
+        let mut tracker = graph.tracker.clone();
+        for wave in self.at(idx).waves.iter().take(waves) {
+            for failure in wave.failures.iter() {
+                tracker.delete(failure.node);
+            }
+        }
+
+        (graph, tracker)
+    }
+
+    /// When and why every node deleted so far was deleted.
+    pub fn failure_history(&self) -> FailureHistory {
+        FailureHistory::new(self)
+    }
+
+    /// Deletions of every iteration so far, linked to the failures that caused them.
+    pub fn cascade_tree(&self) -> CascadeTree {
+        CascadeTree::new(self)
+    }
+
     pub fn set_current_iter(&mut self, idx: usize) {
         assert!(idx < self.iter_count());
         self.current_iter = idx as isize;
@@ -78,11 +236,11 @@ impl State {
         self.get()
     }
 
-    pub fn next(&mut self) -> &Iteration {
+    pub fn next_iter(&mut self) -> &Iteration {
         self.next_by(1)
     }
 
-    pub fn prev(&mut self) -> &Iteration {
+    pub fn prev_iter(&mut self) -> &Iteration {
         self.next_by(-1)
     }
 }
@@ -0,0 +1,248 @@
+use log::{info, warn};
+use rand::{SeedableRng, rngs::StdRng, seq::IteratorRandom};
+
+use crate::{
+    compute::state::{Failure, Iteration, State, Wave, WaveKind},
+    config::{AttackDesc, AttackStrategy, Ranking},
+    graph::{Graph, GraphInfo, node::Node, path_finder::PathFinder},
+};
+
+use super::{ComputeStep, Flow, StepError, paths};
+
+/// Deletes one node of the current iteration chosen by an [`AttackStrategy`]. The run
+/// stops once the strategy runs out of nodes.
+pub struct Attack {
+    desc: AttackDesc,
+    rng: StdRng,
+    order: Option<Vec<Node>>,
+}
+
+impl Attack {
+    pub fn new(desc: AttackDesc) -> Self {
+        let rng = match desc.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        Self {
+            desc,
+            rng,
+            order: None,
+        }
+    }
+
+    /// Score of a node for a targeted strategy, the highest one is attacked first.
+    /// `paths` is only read by closeness.
+    fn score(
+        strategy: AttackStrategy,
+        graph: &Graph,
+        info: &GraphInfo,
+        paths: Option<&PathFinder>,
+        i: Node,
+    ) -> f64 {
+        match strategy {
+            AttackStrategy::MaxBetweenness => info.betweenness[i],
+            AttackStrategy::MaxDegree => graph.degree(i) as f64,
+            AttackStrategy::MaxCloseness => paths.map_or(0.0, |paths| {
+                graph
+                    .tracker
+                    .iter_alive()
+                    .exclude(i)
+                    .map(|j| 1.0 / paths.cost(i, j))
+                    .sum()
+            }),
+            AttackStrategy::MaxCapacity => info.capacity[i],
+            // Zmax leaves the Z of nodes without outgoing paths at 0.
+            AttackStrategy::MinZ if info.zs[i] > 0.0 => -info.zs[i],
+            AttackStrategy::MinZ => f64::NEG_INFINITY,
+            AttackStrategy::Random | AttackStrategy::Targets => 0.0,
+        }
+    }
+
+    /// Alive nodes by decreasing score, the lowest id first on ties.
+    fn rank(
+        strategy: AttackStrategy,
+        graph: &Graph,
+        info: &GraphInfo,
+    ) -> Result<Vec<Node>, StepError> {
+        let paths = match strategy {
+            AttackStrategy::MaxCloseness => Some(paths(graph)?),
+            _ => None,
+        };
+
+        let mut scored = graph
+            .tracker
+            .iter_alive()
+            .map(|i| (Self::score(strategy, graph, info, paths, i), i))
+            .collect::<Vec<_>>();
+        scored.sort_by(|(a, i), (b, j)| b.total_cmp(a).then(i.cmp(j)));
+        Ok(scored.into_iter().map(|(_, i)| i).collect())
+    }
+
+    fn targets(&self, graph: &Graph) -> Vec<Node> {
+        self.desc
+            .targets
+            .iter()
+            .filter(|&&id| {
+                let valid = (id as usize) < graph.node_count();
+                if !valid {
+                    warn!("Ignoring attack target {id}, the graph has no such node");
+                }
+                valid
+            })
+            .map(|&id| unsafe { Node::new(id) })
+            .collect()
+    }
+
+    fn select(&mut self, graph: &Graph, info: &GraphInfo) -> Result<Option<Node>, StepError> {
+        let strategy = self.desc.strategy;
+        Ok(match (strategy, self.desc.ranking) {
+            (AttackStrategy::Random, _) => graph.tracker.iter_alive().choose(&mut self.rng),
+            (AttackStrategy::Targets, _) | (_, Ranking::Initial) => {
+                if self.order.is_none() {
+                    self.order = Some(match strategy {
+                        AttackStrategy::Targets => self.targets(graph),
+                        _ => Self::rank(strategy, graph, info)?,
+                    });
+                }
+
+                self.order
+                    .iter()
+                    .flatten()
+                    .copied()
+                    .find(|&i| graph.tracker.is_alive(i))
+            }
+            (_, Ranking::Recalculated) => Self::rank(strategy, graph, info)?.first().copied(),
+        })
+    }
+}
+
+impl ComputeStep for Attack {
+    fn name(&self) -> &'static str {
+        "attack"
+    }
+
+    fn compute(&mut self, state: &mut State) -> Result<Flow, StepError> {
+        let Iteration { graph, info, waves } = state.get_mut();
+
+        // A pipeline deletes nodes after the last path update of an iteration, so
+        // closeness brings the paths it ranks by up to date itself.
+        let ranks = self.order.is_none() || self.desc.ranking == Ranking::Recalculated;
+        if self.desc.strategy == AttackStrategy::MaxCloseness && ranks && graph.paths().is_none() {
+            graph.update_paths();
+        }
+
+        let Some(node) = self.select(graph, info)? else {
+            return Ok(Flow::Stop(format!(
+                "no node left to attack ({})",
+                self.desc.strategy
+            )));
+        };
+
+        info!("Attacking {node:?} ({})", self.desc.strategy);
+        graph.delete(node);
+        waves.push(Wave {
+            kind: WaveKind::Attack,
+            failures: vec![Failure {
+                node,
+                load: info.betweenness[node],
+                capacity: info.capacity[node],
+            }],
+        });
+
+        Ok(Flow::Continue)
+    }
+}
+
+mod test {
+    #![allow(unused_imports)]
+    use super::Attack;
+    use crate::{
+        compute::{
+            Compute, UpdatePaths,
+            steps::{ComputeStep, zmax::Zmax},
+        },
+        config::{AttackDesc, AttackStrategy, Ranking},
+        graph::{Graph, GraphDesc, GraphInfo, NodeDesc, node::Node},
+    };
+
+    #[test]
+    fn attack_order_test() {
+        let mut graph = Graph::example();
+        graph.update_paths();
+        let info = GraphInfo::new(&graph.tracker);
+
+        let mut attack = Attack::new(AttackDesc {
+            strategy: AttackStrategy::MaxDegree,
+            ..Default::default()
+        });
+        let first = attack.select(&graph, &info).unwrap().unwrap();
+        assert!(
+            graph
+                .tracker
+                .iter_alive()
+                .all(|i| graph.degree(i) <= graph.degree(first))
+        );
+
+        let mut attack = Attack::new(AttackDesc {
+            strategy: AttackStrategy::Targets,
+            ranking: Ranking::Initial,
+            targets: vec![3, 42, 1],
+            ..Default::default()
+        });
+        assert_eq!(
+            attack.select(&graph, &info),
+            Ok(Some(unsafe { Node::new(3) }))
+        );
+        graph.delete(unsafe { Node::new(3) });
+        assert_eq!(
+            attack.select(&graph, &info),
+            Ok(Some(unsafe { Node::new(1) }))
+        );
+        graph.delete(unsafe { Node::new(1) });
+        assert_eq!(attack.select(&graph, &info), Ok(None));
+
+        // Closeness reads the paths, which the deletions made stale.
+        let mut attack = Attack::new(AttackDesc {
+            strategy: AttackStrategy::MaxCloseness,
+            ..Default::default()
+        });
+        assert!(attack.select(&graph, &info).is_err());
+        graph.update_paths();
+        assert!(attack.select(&graph, &info).unwrap().is_some());
+
+        let mut attack = Attack::new(AttackDesc {
+            strategy: AttackStrategy::Random,
+            ..Default::default()
+        });
+        let node = attack.select(&graph, &info).unwrap().unwrap();
+        assert!(graph.tracker.is_alive(node));
+    }
+
+    #[test]
+    fn min_z_skips_nodes_without_paths() {
+        // The path 0 - 1 - 2 - 3 and the isolated node 4, which has no Z.
+        let desc = GraphDesc {
+            nodes: (0..5)
+                .map(|i| NodeDesc {
+                    node_id: i,
+                    nodes: if i < 3 { vec![(i + 1).into()] } else { vec![] },
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let mut compute = Compute::new(desc).unwrap();
+        UpdatePaths.compute(compute.state_mut()).unwrap();
+        Zmax.compute(compute.state_mut()).unwrap();
+
+        let iteration = compute.state().get();
+        let (graph, info) = (&iteration.graph, &iteration.info);
+        let order = Attack::rank(AttackStrategy::MinZ, graph, info).unwrap();
+        let [first, .., last] = order[..] else {
+            panic!("too few nodes ranked")
+        };
+        assert_eq!(first, unsafe { Node::new(0) });
+        assert_eq!(last, unsafe { Node::new(4) });
+    }
+}
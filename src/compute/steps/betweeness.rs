@@ -1,24 +1,116 @@
-use crate::compute::state::{Iteration, State};
+use crate::{
+    compute::state::{Iteration, State},
+    config::BetweennessMode,
+    graph::{
+        Graph, GraphInfo,
+        adjacency::Edge,
+        node::Node,
+        path_finder::{PathFinder, same_cost},
+    },
+};
 
-use super::ComputeStep;
+use super::{ComputeStep, Flow, StepError, paths};
 
-pub struct Betweeness;
+#[derive(Default)]
+pub struct Betweeness {
+    mode: BetweennessMode,
+}
 
-impl ComputeStep for Betweeness {
-    fn compute(&mut self, state: &mut State) -> bool {
-        let Iteration { graph, info } = state.get_mut();
-        info.betweenness.fill(0.0);
+impl Betweeness {
+    /// Largest graph [`BetweennessMode::Legacy`] accepts. Its memberships take n³ bytes,
+    /// 64 MB at this size.
+    pub const LEGACY_MAX_NODES: usize = 400;
 
-        let (mut max, mut max_node) = (0.0, None);
-        let (mut min, mut min_node) = (f64::MAX, None);
+    pub fn new(mode: BetweennessMode) -> Self {
+        Self { mode }
+    }
 
-        for i in graph.tracker.iter_alive() {
-            for (count, s) in graph.tracker.iter_alive().enumerate() {
-                for t in graph.tracker.iter_alive().skip(count + 1) {
-                    info.betweenness[i] += graph.path_finder.contains((s, t), i) as i64 as f64;
+    fn legacy_count(graph: &Graph, paths: &PathFinder, info: &mut GraphInfo) {
+        for (count, s) in graph.tracker.iter_alive().enumerate() {
+            for t in graph.tracker.iter_alive().skip(count + 1) {
+                for i in graph.tracker.iter_alive() {
+                    info.betweenness[i] += paths.is_member((s, t), i) as i64 as f64;
                 }
             }
+        }
+    }
+
+    fn brandes_count(graph: &Graph, path_finder: &PathFinder, info: &mut GraphInfo) {
+        let mut delta = vec![0.0; graph.node_count()];
+
+        for s in graph.tracker.iter_alive() {
+            delta.fill(0.0);
+
+            // Nodes in order of non-increasing distance from `s`, so that every
+            // dependency is complete before it is propagated to the predecessors.
+            let mut order = graph.tracker.iter_alive().exclude(s).collect::<Vec<_>>();
+            order.sort_by(|&a, &b| path_finder.cost(s, b).total_cmp(&path_finder.cost(s, a)));
+
+            for &w in order.iter() {
+                let dist = path_finder.cost(s, w);
+                let sigma = path_finder.count(s, w);
+                if dist.is_infinite() || sigma == 0.0 {
+                    continue;
+                }
+
+                let coeff = (1.0 + delta[w.as_idx()]) / sigma;
+                for Edge { node: v, weight } in graph.predecessors(w) {
+                    if v == s {
+                        continue;
+                    }
+
+                    if same_cost(path_finder.cost(s, v) + weight, dist) {
+                        delta[v.as_idx()] += path_finder.count(s, v) * coeff;
+                    }
+                }
+
+                info.betweenness[w] += delta[w.as_idx()];
+            }
+        }
+
+        // Every unordered pair was visited once from each of its endpoints.
+        if !graph.is_directed() {
+            for i in graph.tracker.iter_alive() {
+                info.betweenness[i] /= 2.0;
+            }
+        }
+    }
+}
+
+impl ComputeStep for Betweeness {
+    fn name(&self) -> &'static str {
+        "betweenness"
+    }
+
+    fn compute(&mut self, state: &mut State) -> Result<Flow, StepError> {
+        let Iteration { graph, info, .. } = state.get_mut();
+        if self.mode == BetweennessMode::Legacy && graph.node_count() > Self::LEGACY_MAX_NODES {
+            return Err(StepError::new(format!(
+                "the legacy betweenness takes graphs of up to {} nodes, use `brandes` for {}",
+                Self::LEGACY_MAX_NODES,
+                graph.node_count()
+            )));
+        }
+
+        paths(graph)?;
+        if self.mode == BetweennessMode::Legacy && graph.record_path_memberships().is_none() {
+            return Err(StepError::new(
+                "a shortest path could not be reconstructed from the paths",
+            ));
+        }
+
+        let paths = paths(graph)?;
+        info.betweenness.fill(0.0);
+
+        match self.mode {
+            BetweennessMode::Brandes => Self::brandes_count(graph, paths, info),
+            BetweennessMode::Legacy => Self::legacy_count(graph, paths, info),
+        }
 
+        let (mut max, mut max_node) = (f64::MIN, None);
+        let (mut min, mut min_node) = (f64::MAX, None::<Node>);
+
+        for i in graph.tracker.iter_alive() {
             if info.betweenness[i] > max {
                 max_node = Some(i);
                 max = info.betweenness[i];
@@ -30,9 +122,109 @@ impl ComputeStep for Betweeness {
             }
         }
 
-        info.max_betweenness = max_node.unwrap();
-        info.min_betweenness = min_node.unwrap();
+        // Nothing is alive once a cascade wipes the graph out, keep the previous nodes.
+        if let (Some(max_node), Some(min_node)) = (max_node, min_node) {
+            info.max_betweenness = max_node;
+            info.min_betweenness = min_node;
+        }
+
+        Ok(Flow::Continue)
+    }
+}
+
+mod test {
+    #![allow(unused_imports)]
+    use super::Betweeness;
+    use crate::{
+        compute::{
+            Compute, UpdatePaths,
+            steps::{ComputeStep, Flow},
+        },
+        config::BetweennessMode,
+        graph::{GraphDesc, NodeDesc, node::Node},
+    };
+
+    #[allow(unused)]
+    fn ring(node_count: u32) -> GraphDesc {
+        GraphDesc {
+            alpha: 0.0,
+            directed: false,
+            betweenness: BetweennessMode::default(),
+            capacity: Default::default(),
+            attack: Default::default(),
+            pipeline: None,
+            nodes: (0..node_count)
+                .map(|i| NodeDesc {
+                    node_id: i,
+                    position: [i as f32, 0.0, 0.0],
+                    nodes: vec![((i + 1) % node_count).into()],
+                })
+                .collect(),
+        }
+    }
+
+    #[allow(unused)]
+    fn betweenness(desc: GraphDesc, mode: BetweennessMode) -> Vec<f64> {
+        let node_count = desc.node_count() as u32;
+        let mut compute = Compute::new(desc).unwrap();
+        assert_eq!(UpdatePaths.compute(compute.state_mut()), Ok(Flow::Continue));
+        assert_eq!(
+            Betweeness::new(mode).compute(compute.state_mut()),
+            Ok(Flow::Continue)
+        );
+
+        let info = &compute.state().get().info;
+        (0..node_count)
+            .map(|i| info.betweenness[unsafe { Node::new(i) }])
+            .collect()
+    }
+
+    #[test]
+    fn brandes_splits_tied_paths() {
+        // In a 4-ring each opposite pair has two shortest paths, one through each
+        // of the remaining nodes.
+        assert_eq!(betweenness(ring(4), BetweennessMode::Brandes), vec![0.5; 4]);
+
+        // In a 5-ring all shortest paths are unique and every node is the middle
+        // of exactly one pair.
+        assert_eq!(betweenness(ring(5), BetweennessMode::Brandes), vec![1.0; 5]);
+    }
+
+    #[test]
+    fn legacy_refuses_large_graphs() {
+        let mut compute = Compute::new(ring(Betweeness::LEGACY_MAX_NODES as u32 + 1)).unwrap();
+        UpdatePaths.compute(compute.state_mut()).unwrap();
+        let result = Betweeness::new(BetweennessMode::Legacy).compute(compute.state_mut());
+        assert!(result.unwrap_err().reason.contains("brandes"));
+
+        assert_eq!(
+            Betweeness::new(BetweennessMode::Brandes).compute(compute.state_mut()),
+            Ok(Flow::Continue)
+        );
+    }
+
+    #[test]
+    fn legacy_counts_one_path_per_direction() {
+        // Walking back from 3 to 0, the scan steps to 2 and goes on with the nodes after
+        // it, stepping to 4 although a new scan would have taken 1 first. So 4 is on
+        // the pair (0, 3) and 1 is not.
+        let neighbours = [vec![1, 4], vec![2], vec![3], vec![], vec![2]];
+        let desc = GraphDesc {
+            nodes: neighbours
+                .into_iter()
+                .enumerate()
+                .map(|(i, nodes)| NodeDesc {
+                    node_id: i as u32,
+                    nodes: nodes.into_iter().map(u32::into).collect(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
 
-        true
+        assert_eq!(
+            betweenness(desc, BetweennessMode::Legacy),
+            vec![5.0, 6.0, 7.0, 4.0, 5.0]
+        );
     }
 }
@@ -1,32 +1,70 @@
-use crate::compute::state::{Iteration, State};
+use crate::{
+    compute::state::{Iteration, State},
+    config::CapacityMode,
+};
 
-use super::ComputeStep;
+use super::{ComputeStep, Flow, StepError};
 
-pub struct Capacity;
+#[derive(Default)]
+pub struct Capacity {
+    mode: CapacityMode,
+}
+
+impl Capacity {
+    pub fn new(mode: CapacityMode) -> Self {
+        Self { mode }
+    }
+}
 
 impl ComputeStep for Capacity {
-    fn compute(&mut self, state: &mut State) -> bool {
+    fn name(&self) -> &'static str {
+        "capacity"
+    }
+
+    fn compute(&mut self, state: &mut State) -> Result<Flow, StepError> {
         let alpha = state.alpha;
-        let Iteration { graph, info } = state.get_mut();
+        if !(alpha >= 0.0 && alpha.is_finite()) {
+            return Err(StepError::new(format!(
+                "alpha must be a non-negative number, got {alpha}"
+            )));
+        }
 
-        let (mut max, mut max_node) = (0.0, None);
+        let initial = (self.mode == CapacityMode::Frozen && state.current_iter() != 0)
+            .then(|| state.at(0).info.capacity.clone());
+
+        let Iteration { graph, info, .. } = state.get_mut();
+
+        let (mut max, mut max_node) = (f64::MIN, None);
         let (mut min, mut min_node) = (f64::MAX, None);
 
         for i in graph.tracker.iter_alive() {
-            info.capacity[i] = (1.0 + alpha) * info.betweenness[i];
+            if let Some(initial) = initial.as_ref() {
+                info.capacity[i] = initial[i];
+            } else if info.betweenness[i].is_finite() {
+                info.capacity[i] = (1.0 + alpha) * info.betweenness[i];
+            } else {
+                return Err(StepError::new(format!(
+                    "betweenness of {i:?} is {}",
+                    info.betweenness[i]
+                )));
+            }
 
             if info.capacity[i] > max {
                 max_node = Some(i);
                 max = info.capacity[i];
-            } else if info.capacity[i] < min {
+            }
+
+            if info.capacity[i] < min {
                 min_node = Some(i);
                 min = info.capacity[i];
             }
         }
 
-        info.max_capacity = max_node.unwrap();
-        info.min_capacity = min_node.unwrap();
+        if let (Some(max_node), Some(min_node)) = (max_node, min_node) {
+            info.max_capacity = max_node;
+            info.min_capacity = min_node;
+        }
 
-        true
+        Ok(Flow::Continue)
     }
 }
@@ -0,0 +1,113 @@
+use log::info;
+
+use crate::{compute::state::State, config::BetweennessMode};
+
+use super::{ComputeStep, Flow, StepError, betweeness::Betweeness, delete::DeleteOverloaded};
+
+/// Runs the cascade an attack triggers to its end: the load is recomputed and the
+/// overloaded nodes deleted until none is overloaded. Every round is recorded as its own
+/// wave, unlike `delete_overloaded` which leaves secondary overloads to the next attack.
+pub struct Cascade {
+    betweenness: Betweeness,
+}
+
+impl Cascade {
+    pub fn new(mode: BetweennessMode) -> Self {
+        Self {
+            betweenness: Betweeness::new(mode),
+        }
+    }
+}
+
+impl ComputeStep for Cascade {
+    fn name(&self) -> &'static str {
+        "cascade"
+    }
+
+    fn compute(&mut self, state: &mut State) -> Result<Flow, StepError> {
+        let mut deleted = 0;
+        let mut waves = 0;
+
+        // Every round deletes at least one node, so this ends with the graph at worst.
+        loop {
+            state.get_mut().graph.update_paths();
+            self.betweenness.compute(state)?;
+
+            let retired = DeleteOverloaded::wave(state);
+            if retired.is_empty() {
+                break;
+            }
+
+            deleted += retired.len();
+            waves += 1;
+        }
+
+        if deleted > 0 {
+            info!("Cascade deleted {deleted} nodes in {waves} waves");
+        }
+        state.record_k();
+
+        Ok(Flow::Continue)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(unused_imports)]
+    use super::Cascade;
+    use crate::{
+        compute::{pipeline::test::shipped, state::WaveKind},
+        graph::GraphDesc,
+    };
+
+    #[test]
+    fn cascade_test() {
+        let mut desc = GraphDesc::example();
+        desc.alpha = 0.2;
+
+        let mut compute = shipped("cascade to steady state").compute(&desc).unwrap();
+        compute.run().unwrap();
+
+        let state = compute.state();
+        assert!(state.iter_count() > 1);
+        assert!(state.at(0).waves.is_empty());
+        assert_eq!(state.ks.len(), state.iter_count());
+        assert!(state.ks[0].is_nan());
+
+        for k in 1..state.iter_count() {
+            let iteration = state.at(k);
+            let (first, rest) = iteration.waves.split_first().unwrap();
+            assert_eq!(first.kind, WaveKind::Attack);
+            assert!(rest.iter().all(|wave| wave.kind == WaveKind::Overload));
+
+            // The cascade only ends once nothing is overloaded.
+            let info = &iteration.info;
+            assert!(
+                iteration
+                    .graph
+                    .tracker
+                    .iter_alive()
+                    .all(|i| info.betweenness[i] <= info.capacity[i])
+            );
+
+            let deleted = iteration
+                .waves
+                .iter()
+                .map(|wave| wave.failures.len())
+                .sum::<usize>();
+            assert_eq!(
+                deleted,
+                state.at(k - 1).graph.alive() - iteration.graph.alive()
+            );
+
+            // One k per iteration, whether or not the attack overloaded anything.
+            match deleted - first.failures.len() {
+                0 => assert!(state.ks[k].is_nan()),
+                overloaded => assert_eq!(state.ks[k], 1.0 / overloaded as f64),
+            }
+
+            let (_, after) = state.after_waves(k, iteration.waves.len());
+            assert_eq!(after.alive(), iteration.graph.alive());
+        }
+    }
+}
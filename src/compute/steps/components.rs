@@ -0,0 +1,81 @@
+use crate::{
+    compute::state::{Iteration, State},
+    graph::components::Components,
+};
+
+use super::{ComputeStep, Flow, StepError};
+
+/// Labels the connected components of the current iteration and records the relative
+/// size of the largest one, G = N' / N, where N is the size of the initial graph.
+pub struct ConnectedComponents;
+
+impl ComputeStep for ConnectedComponents {
+    fn name(&self) -> &'static str {
+        "connected_components"
+    }
+
+    fn compute(&mut self, state: &mut State) -> Result<Flow, StepError> {
+        let g = {
+            let Iteration { graph, info, .. } = state.get_mut();
+            info.components = Components::new(graph);
+
+            info.components.largest_size() as f64 / graph.node_count() as f64
+        };
+
+        state.giant_component.push(g);
+
+        Ok(Flow::Continue)
+    }
+}
+
+mod test {
+    #![allow(unused_imports)]
+    use super::ConnectedComponents;
+    use crate::{
+        compute::{Compute, CopyIteration, steps::ComputeStep},
+        graph::{GraphDesc, NodeDesc, node::Node},
+    };
+
+    #[test]
+    fn giant_component_test() {
+        // The path 0 - 1 - 2 and the edge 3 - 4.
+        let neighbours = [vec![1], vec![2], vec![], vec![4], vec![]];
+        let desc = GraphDesc {
+            nodes: neighbours
+                .into_iter()
+                .enumerate()
+                .map(|(i, nodes)| NodeDesc {
+                    node_id: i as u32,
+                    nodes: nodes.into_iter().map(u32::into).collect(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let [n0, n1, n2, n3, n4] = unsafe { [0, 1, 2, 3, 4].map(|i| Node::new(i)) };
+
+        let mut compute = Compute::new(desc).unwrap();
+        let state = compute.state_mut();
+        ConnectedComponents.compute(state).unwrap();
+
+        let components = &state.get().info.components;
+        assert_eq!(components.sizes(), &[3, 2]);
+        assert_eq!(components.label(n0), components.label(n2));
+        assert_eq!(components.label(n3), components.label(n4));
+        assert_ne!(components.label(n0), components.label(n3));
+
+        // Deleting the middle of the path leaves the edge as the largest component.
+        CopyIteration.compute(state).unwrap();
+        state.get_mut().graph.delete(n1);
+        ConnectedComponents.compute(state).unwrap();
+
+        let components = &state.get().info.components;
+        assert_eq!(components.sizes(), &[1, 1, 2]);
+        assert_ne!(components.label(n0), components.label(n2));
+        assert_eq!(components.largest(), Some(components.label(n3)));
+        assert_eq!(state.giant_component, vec![0.6, 0.4]);
+
+        // The labels of the first iteration are kept with it.
+        assert_eq!(state.at(0).info.components.sizes(), &[3, 2]);
+    }
+}
@@ -1,32 +1,20 @@
 use log::info;
 
-use crate::compute::state::{Iteration, State};
+use crate::{
+    compute::state::{Failure, Iteration, State, Wave, WaveKind},
+    graph::node::Node,
+};
 
-use super::ComputeStep;
-
-pub struct DeleteMaxBetweenness;
-
-impl ComputeStep for DeleteMaxBetweenness {
-    fn compute(&mut self, state: &mut State) -> bool {
-        let Iteration { graph, info } = state.get_mut();
-
-        let value = info.betweenness[info.max_betweenness];
-        info!(
-            "Deleting {:?} with betweenness = {value}",
-            info.max_betweenness
-        );
-
-        graph.delete(info.max_betweenness);
-
-        true
-    }
-}
+use super::{ComputeStep, Flow, StepError};
 
+/// Deletes the nodes whose load exceeds their capacity, once.
 pub struct DeleteOverloaded;
 
-impl ComputeStep for DeleteOverloaded {
-    fn compute(&mut self, state: &mut State) -> bool {
-        let Iteration { graph, info } = state.get_mut();
+impl DeleteOverloaded {
+    /// Deletes the overloaded nodes of the current iteration and records them as a wave.
+    /// Returns the deleted nodes, none once the iteration is steady.
+    pub(super) fn wave(state: &mut State) -> Vec<Node> {
+        let Iteration { graph, info, waves } = state.get_mut();
 
         let mut retired = vec![];
         for i in graph.tracker.iter_alive() {
@@ -44,9 +32,32 @@ impl ComputeStep for DeleteOverloaded {
         }
 
         if !retired.is_empty() {
-            state.ks.push(1.0 / retired.len() as f64);
+            waves.push(Wave {
+                kind: WaveKind::Overload,
+                failures: retired
+                    .iter()
+                    .map(|&node| Failure {
+                        node,
+                        load: info.betweenness[node],
+                        capacity: info.capacity[node],
+                    })
+                    .collect(),
+            });
         }
 
-        true
+        retired
+    }
+}
+
+impl ComputeStep for DeleteOverloaded {
+    fn name(&self) -> &'static str {
+        "delete_overloaded"
+    }
+
+    fn compute(&mut self, state: &mut State) -> Result<Flow, StepError> {
+        Self::wave(state);
+        state.record_k();
+
+        Ok(Flow::Continue)
     }
 }
@@ -0,0 +1,88 @@
+use crate::compute::state::{Iteration, State};
+
+use super::{ComputeStep, Flow, StepError, paths};
+
+/// Global efficiency E = 1/(N(N-1)) Σ 1/d_ij over the alive nodes, where unreachable
+/// pairs contribute 0, and its ratio E / E₀ to the efficiency of the first iteration.
+pub struct Efficiency;
+
+impl ComputeStep for Efficiency {
+    fn name(&self) -> &'static str {
+        "efficiency"
+    }
+
+    fn compute(&mut self, state: &mut State) -> Result<Flow, StepError> {
+        let initial = (state.current_iter() != 0).then(|| state.at(0).info.efficiency);
+
+        let relative = {
+            let Iteration { graph, info, .. } = state.get_mut();
+            let paths = paths(graph)?;
+
+            let mut sum = 0.0;
+            for i in graph.tracker.iter_alive() {
+                for j in graph.tracker.iter_alive().exclude(i) {
+                    sum += 1.0 / paths.cost(i, j);
+                }
+            }
+
+            let n = graph.alive() as f64;
+            info.efficiency = if n > 1.0 { sum / (n * (n - 1.0)) } else { 0.0 };
+
+            let initial = initial.unwrap_or(info.efficiency);
+            info.relative_efficiency = if initial > 0.0 {
+                info.efficiency / initial
+            } else {
+                0.0
+            };
+            info.relative_efficiency
+        };
+
+        state.relative_efficiency.push(relative);
+
+        Ok(Flow::Continue)
+    }
+}
+
+mod test {
+    #![allow(unused_imports)]
+    use super::Efficiency;
+    use crate::{
+        compute::{Compute, CopyIteration, UpdatePaths, steps::ComputeStep},
+        graph::{GraphDesc, NodeDesc, node::Node},
+    };
+
+    #[test]
+    fn efficiency_test() {
+        // A path 0 - 1 - 2: four pairs at distance 1 and two at distance 2.
+        let desc = GraphDesc {
+            nodes: (0..3)
+                .map(|i| NodeDesc {
+                    node_id: i,
+                    nodes: if i < 2 { vec![(i + 1).into()] } else { vec![] },
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let mut compute = Compute::new(desc).unwrap();
+        let state = compute.state_mut();
+        UpdatePaths.compute(state).unwrap();
+        Efficiency.compute(state).unwrap();
+
+        let info = &state.get().info;
+        assert_eq!(info.efficiency, (4.0 + 2.0 * 0.5) / 6.0);
+        assert_eq!(info.relative_efficiency, 1.0);
+
+        // Removing the middle node leaves two unreachable nodes.
+        CopyIteration.compute(state).unwrap();
+        state.get_mut().graph.delete(unsafe { Node::new(1) });
+        UpdatePaths.compute(state).unwrap();
+        Efficiency.compute(state).unwrap();
+
+        let info = &state.get().info;
+        assert_eq!(info.efficiency, 0.0);
+        assert_eq!(info.relative_efficiency, 0.0);
+        assert_eq!(state.relative_efficiency, vec![1.0, 0.0]);
+    }
+}
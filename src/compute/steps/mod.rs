@@ -1,10 +1,56 @@
+use crate::graph::{Graph, path_finder::PathFinder};
+
 use super::state::State;
 
+pub mod attack;
 pub mod betweeness;
 pub mod capacity;
+pub mod cascade;
+pub mod components;
 pub mod delete;
+pub mod efficiency;
 pub mod zmax;
 
-pub trait ComputeStep {
-    fn compute(&mut self, state: &mut State) -> bool;
+/// What the pipeline does after a step.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Flow {
+    Continue,
+    /// Ends the run normally, e.g. when an attack has no node left to remove.
+    Stop(String),
+}
+
+/// A step could not compute its part of the iteration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepError {
+    pub reason: String,
+}
+
+impl StepError {
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Shortest paths of `graph` for a step reading them, an error if they were not updated
+/// since the last deletion.
+pub fn paths(graph: &Graph) -> Result<&PathFinder, StepError> {
+    graph.paths().ok_or_else(|| {
+        StepError::new("the shortest paths are out of date, `update_paths` has to run first")
+    })
+}
+
+/// Steps are `Send` so a pipeline can run on a worker thread.
+pub trait ComputeStep: Send {
+    /// Name used in pipeline files and diagnostics.
+    fn name(&self) -> &'static str;
+
+    fn compute(&mut self, state: &mut State) -> Result<Flow, StepError>;
+
+    /// Whether the step starts a new iteration. The steps before the first such step
+    /// compute the metrics of an iteration, the ones after it attack the new iteration.
+    fn starts_iteration(&self) -> bool {
+        false
+    }
 }
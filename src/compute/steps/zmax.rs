@@ -1,43 +1,68 @@
-use super::ComputeStep;
-use crate::compute::state::{Iteration, State};
+use super::{ComputeStep, Flow, StepError, paths};
+use crate::{
+    compute::state::{Iteration, State},
+    graph::{components::Components, node::NADVec},
+};
 
+/// Computes Z for every node within its connected component, and Zmax and beta for the
+/// largest component. Beta is undefined (NaN) once that component has two nodes or less.
 pub struct Zmax;
 
 impl ComputeStep for Zmax {
-    fn compute(&mut self, state: &mut State) -> bool {
+    fn name(&self) -> &'static str {
+        "zmax"
+    }
+
+    fn compute(&mut self, state: &mut State) -> Result<Flow, StepError> {
         let beta_delta = {
-            let Iteration { graph, info } = state.get_mut();
+            let Iteration { graph, info, .. } = state.get_mut();
+            let components = Components::new(graph);
+            let paths = paths(graph)?;
 
-            let mut sum = 0.0;
+            // Unreachable pairs (other components, or against the arcs of a directed
+            // graph) do not contribute to the distance sums.
+            let mut per_node_sums = NADVec::<f64>::new(&graph.tracker);
+            let mut sums = vec![0.0; components.count()];
             for i in graph.tracker.iter_alive() {
-                for j in graph.tracker.iter_alive().exclude(i) {
-                    sum += graph.path_finder.cost(i, j) as f64;
-                }
+                per_node_sums[i] = graph
+                    .tracker
+                    .iter_alive()
+                    .exclude(i)
+                    .map(|j| paths.cost(i, j))
+                    .filter(|cost| cost.is_finite())
+                    .sum::<f64>();
+
+                sums[components.label(i)] += per_node_sums[i];
             }
 
             info.zs.fill(0.0);
 
             for i in graph.tracker.iter_alive() {
-                let mut per_node_sum = 0.0;
-                for j in graph.tracker.iter_alive().exclude(i) {
-                    per_node_sum += graph.path_finder.cost(i, j) as f64;
+                if per_node_sums[i] > 0.0 {
+                    info.zs[i] = sums[components.label(i)] / (2.0 * per_node_sums[i]);
                 }
-
-                info.zs[i] = sum / (2.0 * per_node_sum);
             }
 
-            let n = graph.alive() as f64;
-            info.zmax = unsafe {
-                let zs = info.zs.clone_vec();
-                zs.into_iter().reduce(f64::max).unwrap()
-            };
+            let giant = components.largest();
+            let n = components.largest_size() as f64;
+            info.zmax = graph
+                .tracker
+                .iter_alive()
+                .filter(|&i| Some(components.label(i)) == giant)
+                .map(|i| info.zs[i])
+                .reduce(f64::max)
+                .unwrap_or(0.0);
 
-            info.beta = ((n - 1.0) * (2.0 * info.zmax - n)) / (info.zmax * (n - 2.0));
+            info.beta = if n > 2.0 {
+                ((n - 1.0) * (2.0 * info.zmax - n)) / (info.zmax * (n - 2.0))
+            } else {
+                f64::NAN
+            };
             (info.beta - 1.0).abs()
         };
 
         state.beta_deltas.push(beta_delta);
 
-        true
+        Ok(Flow::Continue)
     }
 }
@@ -0,0 +1,155 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::graph::components::Components;
+
+use super::state::State;
+
+/// A condition that ends [`super::Compute::run`]. Conditions are checked before every
+/// pass of the pipeline against the current iteration, the initial one included.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StopCondition {
+    /// Stop once this many iterations, including the initial one, were computed.
+    MaxIterations(usize),
+    /// Stop once fewer than this many nodes are alive.
+    MinAlive(usize),
+    /// Stop once the fraction of alive nodes drops below the threshold.
+    MinAliveFraction(f64),
+    /// Stop once a pass does not delete any node.
+    SteadyState,
+    /// Stop once the largest connected component, relative to the initial node count,
+    /// drops below the threshold.
+    GiantComponentBelow(f64),
+}
+
+impl StopCondition {
+    pub fn is_met(&self, state: &State) -> bool {
+        let graph = &state.get().graph;
+
+        match *self {
+            Self::MaxIterations(count) => state.iter_count() >= count,
+            Self::MinAlive(count) => graph.alive() < count,
+            Self::MinAliveFraction(fraction) => {
+                (graph.alive() as f64 / graph.node_count() as f64) < fraction
+            }
+            Self::SteadyState => Self::is_steady(state),
+            Self::GiantComponentBelow(fraction) => {
+                let largest = Components::new(graph).largest_size();
+                (largest as f64 / graph.node_count() as f64) < fraction
+            }
+        }
+    }
+
+    /// Whether the current iteration has the same nodes as the previous one.
+    pub(super) fn is_steady(state: &State) -> bool {
+        let current = state.current_iter();
+        current > 0 && state.at(current).graph.alive() == state.at(current - 1).graph.alive()
+    }
+}
+
+impl fmt::Display for StopCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MaxIterations(count) => write!(f, "reached {count} iterations"),
+            Self::MinAlive(count) => write!(f, "fewer than {count} nodes alive"),
+            Self::MinAliveFraction(fraction) => {
+                write!(f, "fewer than {:.1}% of nodes alive", fraction * 100.0)
+            }
+            Self::SteadyState => write!(f, "no node deleted"),
+            Self::GiantComponentBelow(fraction) => {
+                write!(f, "giant component below {:.1}%", fraction * 100.0)
+            }
+        }
+    }
+}
+
+/// The `stop` table of a pipeline, replacing the default conditions of
+/// [`super::Compute`]. Every field set adds its condition.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StopDesc {
+    pub max_iterations: Option<usize>,
+    pub min_alive: Option<usize>,
+    pub min_alive_fraction: Option<f64>,
+    #[serde(default)]
+    pub steady_state: bool,
+    pub giant_component_below: Option<f64>,
+}
+
+impl StopDesc {
+    /// The conditions of the table, an error if it has none or a fraction is outside
+    /// `0..=1`.
+    pub fn conditions(&self) -> Result<Vec<StopCondition>, String> {
+        for (name, fraction) in [
+            ("min_alive_fraction", self.min_alive_fraction),
+            ("giant_component_below", self.giant_component_below),
+        ] {
+            if let Some(fraction) = fraction.filter(|f| !(0.0..=1.0).contains(f)) {
+                return Err(format!("`{name}` is {fraction}, not a fraction"));
+            }
+        }
+
+        let conditions = [
+            self.max_iterations.map(StopCondition::MaxIterations),
+            self.min_alive.map(StopCondition::MinAlive),
+            self.min_alive_fraction.map(StopCondition::MinAliveFraction),
+            self.steady_state.then_some(StopCondition::SteadyState),
+            self.giant_component_below
+                .map(StopCondition::GiantComponentBelow),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+        if conditions.is_empty() {
+            return Err("no condition is set".to_owned());
+        }
+
+        Ok(conditions)
+    }
+}
+
+/// Why [`super::Compute::run`] finished.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StopReason {
+    Condition(StopCondition),
+    /// A step asked to stop.
+    Step {
+        step: &'static str,
+        reason: String,
+    },
+    Cancelled,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Condition(condition) => write!(f, "{condition}"),
+            Self::Step { step, reason } => write!(f, "{reason} (`{step}`)"),
+            Self::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+mod test {
+    #![allow(unused_imports)]
+    use super::{StopCondition, StopDesc};
+
+    #[test]
+    fn stop_desc_test() {
+        let desc: StopDesc = toml::from_str("max_iterations = 2\nsteady_state = true").unwrap();
+        assert_eq!(
+            desc.conditions(),
+            Ok(vec![
+                StopCondition::MaxIterations(2),
+                StopCondition::SteadyState
+            ])
+        );
+
+        assert!(StopDesc::default().conditions().is_err());
+        let desc: StopDesc = toml::from_str("min_alive_fraction = 1.5").unwrap();
+        assert!(desc.conditions().is_err());
+        assert!(toml::from_str::<StopDesc>("min_nodes = 3").is_err());
+    }
+}
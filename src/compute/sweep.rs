@@ -0,0 +1,196 @@
+use std::{ops::RangeInclusive, sync::atomic::AtomicBool};
+
+use crate::graph::GraphDesc;
+
+use super::{
+    ComputeError,
+    batch::Batch,
+    pipeline::{Pipeline, PipelineDesc, Registry, StepDesc},
+    stop::StopDesc,
+};
+
+/// Outcome of one attack and the cascade it triggers for one alpha.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SweepPoint {
+    pub alpha: f64,
+    /// Alive nodes after the cascade relative to the initial count.
+    pub alive_fraction: f64,
+    /// Largest component after the cascade relative to the initial count, G.
+    pub giant_component: f64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SweepResult {
+    /// Sorted by alpha, including the points added by the bisection.
+    pub points: Vec<SweepPoint>,
+    pub threshold: f64,
+    /// Estimated lowest alpha for which G stays above the threshold. `None` if the
+    /// network collapses, or survives, over the whole range.
+    pub critical_alpha: Option<f64>,
+}
+
+/// Attacks the graph once for evenly spaced alphas and runs the cascade to its end,
+/// then bisects the first interval where G after the cascade crosses the threshold.
+/// G is assumed to grow with alpha. The attack is the graph's, not a pipeline's, and
+/// repeated attacks are left out since they take any network down in the end.
+pub struct Sweep {
+    alphas: RangeInclusive<f64>,
+    steps: usize,
+    threshold: f64,
+    pipeline: Pipeline,
+}
+
+impl Sweep {
+    /// Number of times the transition interval is halved.
+    const BISECTIONS: usize = 6;
+
+    pub fn new(alphas: RangeInclusive<f64>, steps: usize, threshold: f64) -> Self {
+        Self {
+            alphas,
+            steps: steps.max(2),
+            threshold,
+            pipeline: Registry::default()
+                .validate(&Self::pipeline())
+                .expect("The sweep pipeline is valid"),
+        }
+    }
+
+    /// Metrics of the initial graph, one attack and its cascade. The run stops on the
+    /// second iteration, computing its metrics.
+    fn pipeline() -> PipelineDesc {
+        PipelineDesc {
+            name: "sweep".to_owned(),
+            steps: [
+                "update_paths",
+                "connected_components",
+                "zmax",
+                "betweenness",
+                "capacity",
+                "copy_iteration",
+                "attack",
+                "cascade",
+            ]
+            .map(StepDesc::new)
+            .to_vec(),
+            stop: Some(StopDesc {
+                max_iterations: Some(2),
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Runs of the pipeline if the bisection is needed, fewer otherwise.
+    pub fn max_runs(&self) -> usize {
+        self.steps + Self::BISECTIONS
+    }
+
+    /// Sweeps the alphas of `desc`, keeping the rest of the graph as it is.
+    pub fn run(&self, desc: &GraphDesc) -> Result<SweepResult, ComputeError> {
+        self.run_with(desc, &AtomicBool::new(false), |_| {})
+            .map(|result| result.expect("the sweep is not cancelled"))
+    }
+
+    /// [`Self::run`] that gives up once `cancel` is set, returning `None`. `progress`
+    /// gets the number of alphas done, at most [`Self::max_runs`] with the bisection.
+    pub fn run_with(
+        &self,
+        desc: &GraphDesc,
+        cancel: &AtomicBool,
+        progress: impl FnMut(usize),
+    ) -> Result<Option<SweepResult>, ComputeError> {
+        let mut batch = Batch::new(cancel, progress);
+        let mut point = |alpha: f64| -> Result<Option<SweepPoint>, ComputeError> {
+            let mut desc = desc.clone();
+            desc.alpha = alpha;
+
+            let Some(compute) = batch.run(self.pipeline.compute(&desc)?)? else {
+                return Ok(None);
+            };
+
+            let state = compute.state();
+            let graph = &state.at(state.iter_count() - 1).graph;
+            Ok(Some(SweepPoint {
+                alpha,
+                alive_fraction: graph.alive() as f64 / graph.node_count() as f64,
+                giant_component: state.giant_component.last().copied().unwrap_or_default(),
+            }))
+        };
+
+        let (from, to) = (*self.alphas.start(), *self.alphas.end());
+        let mut points = vec![];
+        for i in 0..self.steps {
+            let alpha = from + (to - from) * i as f64 / (self.steps - 1) as f64;
+            let Some(next) = point(alpha)? else {
+                return Ok(None);
+            };
+            points.push(next);
+        }
+
+        let survives = |p: &SweepPoint| p.giant_component >= self.threshold;
+        let transition = points
+            .windows(2)
+            .find(|pair| !survives(&pair[0]) && survives(&pair[1]))
+            .map(|pair| (pair[0].alpha, pair[1].alpha));
+
+        let critical_alpha = match transition {
+            Some((mut collapsed, mut survived)) => {
+                for _ in 0..Self::BISECTIONS {
+                    let Some(mid) = point((collapsed + survived) / 2.0)? else {
+                        return Ok(None);
+                    };
+                    if survives(&mid) {
+                        survived = mid.alpha;
+                    } else {
+                        collapsed = mid.alpha;
+                    }
+                    points.push(mid);
+                }
+                Some((collapsed + survived) / 2.0)
+            }
+            None => None,
+        };
+
+        points.sort_by(|a, b| a.alpha.total_cmp(&b.alpha));
+
+        Ok(Some(SweepResult {
+            points,
+            threshold: self.threshold,
+            critical_alpha,
+        }))
+    }
+}
+
+mod test {
+    #![allow(unused_imports)]
+    use super::Sweep;
+    use crate::graph::GraphDesc;
+
+    #[test]
+    fn sweep_test() {
+        let result = Sweep::new(0.0..=4.0, 5, 0.5)
+            .run(&GraphDesc::example())
+            .unwrap();
+
+        assert!(result.points.len() > 5);
+        assert!(result.points.windows(2).all(|p| p[0].alpha <= p[1].alpha));
+
+        // Without any tolerance the first attack overloads most of the example, with
+        // enough of it only the attacked node fails.
+        let (first, last) = (result.points[0], result.points[result.points.len() - 1]);
+        assert!(first.giant_component < 0.5);
+        assert!(last.giant_component > 0.5);
+        assert!(
+            result
+                .points
+                .windows(2)
+                .all(|p| p[0].giant_component <= p[1].giant_component)
+        );
+
+        let critical = result.critical_alpha.unwrap();
+        assert!(0.0 < critical && critical < 1.0);
+        let below = result.points.iter().rfind(|p| p.alpha < critical);
+        let above = result.points.iter().find(|p| p.alpha > critical);
+        assert!(below.unwrap().giant_component < 0.5);
+        assert!(above.unwrap().giant_component >= 0.5);
+    }
+}
@@ -0,0 +1,277 @@
+use serde::Serialize;
+
+use crate::graph::{Graph, node::Node};
+
+use super::state::{State, WaveKind};
+
+/// A node deleted during a run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CascadeEvent {
+    pub node: Node,
+    /// Iteration the node was deleted while computing.
+    pub iteration: usize,
+    /// Wave of the iteration, the attack is wave 0 when the pipeline attacks.
+    pub wave: usize,
+    pub cause: WaveKind,
+    pub load: f64,
+    pub capacity: f64,
+    /// Load of the node at the start of the iteration, before the attack.
+    pub load_before: f64,
+    /// Events of the previous wave of the iteration that lay on a shortest path now
+    /// running through the node, if its load rose above `load_before`. Empty for
+    /// attacks.
+    pub causes: Vec<usize>,
+}
+
+/// Every deletion of a run linked to the deletions that raised its load. Attacks are
+/// the roots, the first cause of a failure is its parent.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CascadeTree {
+    events: Vec<CascadeEvent>,
+}
+
+/// One line of an exported tree, nodes by their id in the graph file.
+#[derive(Serialize)]
+struct EventRecord {
+    node: u32,
+    iteration: usize,
+    wave: usize,
+    cause: WaveKind,
+    load: f64,
+    capacity: f64,
+    load_before: f64,
+    caused_by: Vec<u32>,
+}
+
+#[derive(Serialize)]
+struct TreeRecord {
+    failures: Vec<EventRecord>,
+}
+
+impl CascadeTree {
+    /// Replays the waves of every iteration on a copy of its graph, so the shortest
+    /// paths are recomputed once per wave.
+    pub fn new(state: &State) -> Self {
+        let mut events: Vec<CascadeEvent> = vec![];
+
+        for iteration in 1..state.iter_count() {
+            let before = &state.at(iteration - 1).info.betweenness;
+            let waves = &state.at(iteration).waves;
+            let mut graph = state.at(iteration - 1).graph.clone();
+            let mut previous_wave = None::<(std::ops::Range<usize>, Graph)>;
+
+            for (wave, w) in waves.iter().enumerate() {
+                let start = events.len();
+                if previous_wave.is_some() || wave + 1 < waves.len() {
+                    graph.update_paths();
+                }
+
+                for failure in w.failures.iter() {
+                    let load_before = before[failure.node];
+                    let causes = match (&previous_wave, w.kind) {
+                        (Some((previous, pre_wave)), WaveKind::Overload)
+                            if failure.load > load_before =>
+                        {
+                            previous
+                                .clone()
+                                .filter(|&cause| {
+                                    rerouted(pre_wave, &graph, events[cause].node, failure.node)
+                                })
+                                .collect()
+                        }
+                        _ => vec![],
+                    };
+
+                    events.push(CascadeEvent {
+                        node: failure.node,
+                        iteration,
+                        wave,
+                        cause: w.kind,
+                        load: failure.load,
+                        capacity: failure.capacity,
+                        load_before,
+                        causes,
+                    });
+                }
+
+                let pre_wave = graph.split_off();
+                for failure in w.failures.iter() {
+                    graph.delete(failure.node);
+                }
+                previous_wave = Some((start..events.len(), pre_wave));
+            }
+        }
+
+        Self { events }
+    }
+
+    pub fn events(&self) -> &[CascadeEvent] {
+        &self.events
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Events without causes: the attacks and overloads not explained by a failure.
+    pub fn roots(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.events.len()).filter(|&i| self.events[i].causes.is_empty())
+    }
+
+    /// Events whose first cause is `event`.
+    pub fn children(&self, event: usize) -> impl Iterator<Item = usize> + '_ {
+        (event + 1..self.events.len())
+            .filter(move |&i| self.events[i].causes.first() == Some(&event))
+    }
+
+    /// The event deleting `node`, a node is only deleted once.
+    pub fn find(&self, node: Node) -> Option<usize> {
+        self.events.iter().position(|event| event.node == node)
+    }
+
+    /// The first causes leading to `event`, from its root to the event itself.
+    pub fn explain(&self, event: usize) -> Vec<usize> {
+        let mut chain = vec![event];
+        while let Some(&cause) = self.events[*chain.last().unwrap()].causes.first() {
+            chain.push(cause);
+        }
+
+        chain.reverse();
+        chain
+    }
+
+    pub fn to_toml(&self) -> Result<String, toml::ser::Error> {
+        let id = |node: Node| node.as_idx() as u32;
+
+        toml::to_string(&TreeRecord {
+            failures: self
+                .events
+                .iter()
+                .map(|event| EventRecord {
+                    node: id(event.node),
+                    iteration: event.iteration,
+                    wave: event.wave,
+                    cause: event.cause,
+                    load: event.load,
+                    capacity: event.capacity,
+                    load_before: event.load_before,
+                    caused_by: event
+                        .causes
+                        .iter()
+                        .map(|&cause| id(self.events[cause].node))
+                        .collect(),
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Whether a pair of alive nodes other than `node` had a shortest path through `cause`
+/// before its wave and has one through `node` after it, so the pair's share of the load
+/// moved from `cause` onto `node`. Both graphs need up to date paths.
+fn rerouted(pre_wave: &Graph, graph: &Graph, cause: Node, node: Node) -> bool {
+    let (Some(before), Some(after)) = (pre_wave.paths(), graph.paths()) else {
+        return false;
+    };
+
+    graph.tracker.iter_alive().filter(|&s| s != node).any(|s| {
+        graph.tracker.iter_alive().any(|t| {
+            t != s && t != node && before.contains((s, t), cause) && after.contains((s, t), node)
+        })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(unused_imports)]
+    use super::{CascadeTree, rerouted};
+    use crate::{
+        compute::{pipeline::test::shipped, state::WaveKind},
+        graph::{Graph, GraphDesc, NodeDesc, node::Node},
+    };
+
+    #[test]
+    fn cascade_tree_test() {
+        let mut desc = GraphDesc::example();
+        desc.alpha = 0.2;
+
+        let mut compute = shipped("cascade to steady state").compute(&desc).unwrap();
+        compute.run().unwrap();
+
+        let state = compute.state();
+        let tree = state.cascade_tree();
+        let deleted = state.at(0).graph.alive() - state.at(state.iter_count() - 1).graph.alive();
+        assert_eq!(tree.events().len(), deleted);
+
+        for (i, event) in tree.events().iter().enumerate() {
+            assert_eq!(tree.find(event.node), Some(i));
+            match event.cause {
+                WaveKind::Attack => assert!(event.causes.is_empty()),
+                WaveKind::Overload => assert!(event.load > event.capacity),
+            }
+
+            for &cause in event.causes.iter() {
+                let cause = &tree.events()[cause];
+                assert_eq!(cause.iteration, event.iteration);
+                assert_eq!(cause.wave + 1, event.wave);
+                assert!(event.load > event.load_before);
+            }
+
+            let chain = tree.explain(i);
+            assert_eq!(chain.last(), Some(&i));
+            assert!(tree.events()[chain[0]].causes.is_empty());
+        }
+
+        assert!(tree.events().iter().any(|event| !event.causes.is_empty()));
+
+        let roots = tree.roots().count();
+        let children = (0..tree.events().len())
+            .map(|i| tree.children(i).count())
+            .sum::<usize>();
+        assert_eq!(roots + children, tree.events().len());
+
+        let exported: toml::Table = toml::from_str(&tree.to_toml().unwrap()).unwrap();
+        assert_eq!(exported["failures"].as_array().unwrap().len(), deleted);
+    }
+
+    #[test]
+    fn rerouted_test() {
+        // The squares 0 - 1 - 2 - 3 and 4 - 5 - 6 - 7.
+        let neighbours = [
+            vec![1, 3],
+            vec![2],
+            vec![3],
+            vec![],
+            vec![5, 7],
+            vec![6],
+            vec![7],
+            vec![],
+        ];
+        let desc = GraphDesc {
+            nodes: neighbours
+                .into_iter()
+                .enumerate()
+                .map(|(i, nodes)| NodeDesc {
+                    node_id: i as u32,
+                    nodes: nodes.into_iter().map(u32::into).collect(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let [n1, n3, n5, n7] = unsafe { [1, 3, 5, 7].map(|i| Node::new(i)) };
+
+        let mut graph = Graph::try_from(desc).unwrap();
+        graph.update_paths();
+        let pre_wave = graph.split_off();
+        graph.delete(n1);
+        graph.delete(n5);
+        graph.update_paths();
+
+        // Only the paths of its own square move onto a node.
+        assert!(rerouted(&pre_wave, &graph, n1, n3));
+        assert!(!rerouted(&pre_wave, &graph, n5, n3));
+        assert!(rerouted(&pre_wave, &graph, n5, n7));
+        assert!(!rerouted(&pre_wave, &graph, n1, n7));
+    }
+}
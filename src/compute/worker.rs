@@ -0,0 +1,258 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+    thread,
+};
+
+use crate::graph::GraphDesc;
+
+use super::{
+    Compute, ComputeError,
+    ensemble::{Ensemble, EnsembleResult},
+    pipeline::Pipeline,
+    state::StateUpdate,
+    stop::StopReason,
+    sweep::{Sweep, SweepResult},
+};
+
+pub enum ComputeEvent {
+    Progress(StateUpdate),
+    Finished(Result<StopReason, ComputeError>),
+    /// Runs of an ensemble or a sweep finished so far, out of at most `total`.
+    Runs {
+        finished: usize,
+        total: usize,
+    },
+    /// `None` if the ensemble was cancelled.
+    EnsembleFinished(Result<Option<EnsembleResult>, ComputeError>),
+    /// `None` if the sweep was cancelled.
+    SweepFinished(Result<Option<SweepResult>, ComputeError>),
+}
+
+/// Runs a [`Compute`], an [`Ensemble`] or a [`Sweep`] on its own thread and streams
+/// its progress. A job always ends with its finished event, a panic included.
+/// Dropping the worker cancels the job without waiting for the pass in progress.
+pub struct ComputeWorker {
+    events: Receiver<ComputeEvent>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl ComputeWorker {
+    pub fn spawn(mut compute: Compute) -> Self {
+        Self::spawn_job(move |sender, cancel| {
+            let mut sent = 0;
+            let result = Self::catch_panic(|| {
+                compute.run_with(cancel, |state, finished| {
+                    // The UI may already be gone, the run is cancelled then anyway.
+                    let _ = sender.send(ComputeEvent::Progress(state.update(sent, finished)));
+                    sent = finished;
+                })
+            });
+
+            let state = compute.state();
+            let _ = sender.send(ComputeEvent::Progress(
+                state.update(sent, state.iter_count()),
+            ));
+            ComputeEvent::Finished(result)
+        })
+    }
+
+    pub fn spawn_ensemble(ensemble: Ensemble, desc: GraphDesc, pipeline: Pipeline) -> Self {
+        Self::spawn_job(move |sender, cancel| {
+            let total = ensemble.runs();
+            ComputeEvent::EnsembleFinished(Self::catch_panic(|| {
+                ensemble.run_with(
+                    &desc,
+                    |desc| pipeline.compute(desc),
+                    cancel,
+                    |finished| {
+                        let _ = sender.send(ComputeEvent::Runs { finished, total });
+                    },
+                )
+            }))
+        })
+    }
+
+    pub fn spawn_sweep(sweep: Sweep, desc: GraphDesc) -> Self {
+        Self::spawn_job(move |sender, cancel| {
+            let total = sweep.max_runs();
+            ComputeEvent::SweepFinished(Self::catch_panic(|| {
+                sweep.run_with(&desc, cancel, |finished| {
+                    let _ = sender.send(ComputeEvent::Runs { finished, total });
+                })
+            }))
+        })
+    }
+
+    /// Runs `job` on a new thread and sends the event it ends with.
+    fn spawn_job(
+        job: impl FnOnce(&Sender<ComputeEvent>, &AtomicBool) -> ComputeEvent + Send + 'static,
+    ) -> Self {
+        let (sender, events) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        thread::spawn({
+            let cancel = cancel.clone();
+            move || {
+                let finished = job(&sender, &cancel);
+                let _ = sender.send(finished);
+            }
+        });
+
+        Self { events, cancel }
+    }
+
+    fn catch_panic<T>(job: impl FnOnce() -> Result<T, ComputeError>) -> Result<T, ComputeError> {
+        panic::catch_unwind(AssertUnwindSafe(job)).unwrap_or_else(|payload| {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            Err(ComputeError::Panicked(message))
+        })
+    }
+
+    /// Asks the run to stop after the pass in progress.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    /// Events received since the last call, without blocking.
+    pub fn poll(&self) -> impl Iterator<Item = ComputeEvent> + '_ {
+        self.events.try_iter()
+    }
+}
+
+impl Drop for ComputeWorker {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(unused_imports)]
+    use super::{ComputeEvent, ComputeWorker};
+    use crate::{
+        compute::{
+            Compute, ComputeError, CopyIteration, UpdatePaths,
+            ensemble::Ensemble,
+            pipeline::{PipelineDesc, Registry, test::default_compute},
+            state::State,
+            steps::{ComputeStep, Flow, StepError, attack::Attack, betweeness::Betweeness},
+            sweep::Sweep,
+        },
+        config::BetweennessMode,
+        graph::GraphDesc,
+    };
+
+    #[test]
+    fn worker_test() {
+        let desc = GraphDesc::example();
+        let mut expected = default_compute(&desc);
+        let expected_reason = expected.run().unwrap();
+
+        let worker = ComputeWorker::spawn(default_compute(&desc));
+        let mut state = State::initial(desc).unwrap();
+        let reason = loop {
+            match worker.events.recv().unwrap() {
+                ComputeEvent::Progress(update) => state.apply(update),
+                ComputeEvent::Finished(result) => break result.unwrap(),
+                _ => {}
+            }
+        };
+
+        assert_eq!(reason, expected_reason);
+        assert_eq!(state.iter_count(), expected.state().iter_count());
+        assert_eq!(state.beta_deltas.len(), expected.state().beta_deltas.len());
+        for i in 0..state.iter_count() {
+            assert_eq!(
+                state.at(i).graph.alive(),
+                expected.state().at(i).graph.alive()
+            );
+        }
+    }
+
+    #[test]
+    fn batch_test() {
+        let pipeline = Registry::default()
+            .validate(&PipelineDesc::default())
+            .unwrap();
+
+        let worker =
+            ComputeWorker::spawn_ensemble(Ensemble::new(3, 7), GraphDesc::example(), pipeline);
+        let mut runs = vec![];
+        let result = loop {
+            match worker.events.recv().unwrap() {
+                ComputeEvent::Runs { finished, total } => runs.push((finished, total)),
+                ComputeEvent::EnsembleFinished(result) => break result.unwrap().unwrap(),
+                _ => {}
+            }
+        };
+        assert_eq!(runs, vec![(1, 3), (2, 3), (3, 3)]);
+        assert_eq!(result.seeds, vec![7, 8, 9]);
+
+        let worker =
+            ComputeWorker::spawn_sweep(Sweep::new(0.0..=1.0, 2, 0.5), GraphDesc::example());
+        let result = loop {
+            if let ComputeEvent::SweepFinished(result) = worker.events.recv().unwrap() {
+                break result.unwrap().unwrap();
+            }
+        };
+        assert!(result.points.len() >= 2);
+    }
+
+    #[allow(unused)]
+    fn result(compute: Compute) -> Result<(), ComputeError> {
+        let worker = ComputeWorker::spawn(compute);
+        loop {
+            if let ComputeEvent::Finished(result) = worker.events.recv().unwrap() {
+                break result.map(|_| ());
+            }
+        }
+    }
+
+    #[allow(unused)]
+    struct Panic;
+
+    impl ComputeStep for Panic {
+        fn name(&self) -> &'static str {
+            "panic"
+        }
+
+        fn compute(&mut self, _: &mut State) -> Result<Flow, StepError> {
+            panic!("bug in a step")
+        }
+    }
+
+    #[test]
+    fn worker_reports_failures() {
+        let desc = GraphDesc::example();
+
+        // The attack leaves the paths stale for the betweenness.
+        let stale = Compute::new(desc.clone())
+            .unwrap()
+            .add_step(UpdatePaths)
+            .add_step(CopyIteration)
+            .add_step(Attack::new(desc.attack.clone()))
+            .add_step(Betweeness::new(BetweennessMode::Legacy));
+        assert!(matches!(
+            result(stale),
+            Err(ComputeError::Step {
+                step: "betweenness",
+                ..
+            })
+        ));
+
+        let panics = Compute::new(desc).unwrap().add_step(Panic);
+        assert_eq!(
+            result(panics),
+            Err(ComputeError::Panicked("bug in a step".into()))
+        );
+    }
+}
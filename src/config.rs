@@ -0,0 +1,119 @@
+//! Parameters of a run that a graph file can set, read by the compute steps.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// How a node's load is counted from the shortest paths of the current graph.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BetweennessMode {
+    /// Brandes' algorithm: every shortest path between a pair is counted and a node
+    /// receives the fraction of them it lies on.
+    #[default]
+    Brandes,
+    /// Counts 1 for every pair one of whose reconstructed paths (in either direction)
+    /// went through the node at any point of the run, endpoints included. Kept to
+    /// reproduce results computed before `Brandes` was added. Remembering the pairs
+    /// takes n³ bytes, so graphs of more than
+    /// [`Betweeness::LEGACY_MAX_NODES`](crate::compute::steps::betweeness::Betweeness::LEGACY_MAX_NODES)
+    /// nodes are refused.
+    Legacy,
+}
+
+impl BetweennessMode {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// How capacities follow the load of the nodes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CapacityMode {
+    /// C_i = (1 + alpha) L_i(0): computed on the initial graph and kept through the
+    /// cascade, as in the Motter–Lai model.
+    #[default]
+    Frozen,
+    /// C_i = (1 + alpha) L_i from the load of the current iteration, so capacity grows
+    /// with the load. This was the only behaviour before `Frozen` was added.
+    Adaptive,
+}
+
+impl CapacityMode {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Which node an attack removes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttackStrategy {
+    /// A uniformly random alive node, i.e. a random failure.
+    Random,
+    #[default]
+    MaxBetweenness,
+    /// Counts both arcs of a directed graph.
+    MaxDegree,
+    /// Harmonic closeness Σ 1/d(i, j), so unreachable nodes do not make it zero.
+    MaxCloseness,
+    MaxCapacity,
+    /// Lowest Z first. Nodes without any outgoing path, e.g. isolated nodes or the sinks
+    /// of a directed graph, have no Z and come last.
+    MinZ,
+    /// The `targets` of the attack description, in order.
+    Targets,
+}
+
+impl fmt::Display for AttackStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Random => "random",
+            Self::MaxBetweenness => "max betweenness",
+            Self::MaxDegree => "max degree",
+            Self::MaxCloseness => "max closeness",
+            Self::MaxCapacity => "max capacity",
+            Self::MinZ => "min Z",
+            Self::Targets => "targets",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// When the nodes are ranked by a targeted strategy.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Ranking {
+    /// Ranks the nodes of the current iteration before every attack.
+    #[default]
+    Recalculated,
+    /// Ranks the nodes once, on the first attack, and follows that order.
+    Initial,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct AttackDesc {
+    #[serde(default)]
+    pub strategy: AttackStrategy,
+    #[serde(default, skip_serializing_if = "Ranking::is_default")]
+    pub ranking: Ranking,
+    /// Node ids attacked by [`AttackStrategy::Targets`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<u32>,
+    /// Seed of [`AttackStrategy::Random`], a fresh one is drawn when missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl Ranking {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl AttackDesc {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
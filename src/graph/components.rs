@@ -0,0 +1,109 @@
+use super::{
+    Graph,
+    adjacency::Edge,
+    node::{NADVec, Node},
+};
+
+/// Connected components of the alive part of a graph. Directed graphs are split into
+/// weakly connected components, i.e. arc directions are ignored.
+#[derive(Clone, Debug, Default)]
+pub struct Components {
+    labels: NADVec<usize>,
+    sizes: Vec<usize>,
+}
+
+impl Components {
+    /// Labels components in order of their lowest node id.
+    pub fn new(graph: &Graph) -> Self {
+        let mut labels = NADVec::new(&graph.tracker);
+        let mut visited = vec![false; graph.node_count()];
+        let mut sizes = vec![];
+        let mut stack = vec![];
+
+        for root in graph.tracker.iter_alive() {
+            if visited[root.as_idx()] {
+                continue;
+            }
+
+            let label = sizes.len();
+            let mut size = 0;
+
+            visited[root.as_idx()] = true;
+            stack.push(root);
+
+            while let Some(node) = stack.pop() {
+                labels[node] = label;
+                size += 1;
+
+                for Edge { node: next, .. } in
+                    graph.neighbours(node).chain(graph.predecessors(node))
+                {
+                    if !visited[next.as_idx()] {
+                        visited[next.as_idx()] = true;
+                        stack.push(next);
+                    }
+                }
+            }
+
+            sizes.push(size);
+        }
+
+        Self { labels, sizes }
+    }
+
+    /// Component id of an alive node.
+    pub fn label(&self, node: Node) -> usize {
+        self.labels[node]
+    }
+
+    pub fn count(&self) -> usize {
+        self.sizes.len()
+    }
+
+    pub fn size(&self, label: usize) -> usize {
+        self.sizes[label]
+    }
+
+    pub fn sizes(&self) -> &[usize] {
+        self.sizes.as_slice()
+    }
+
+    /// Id of the largest component, the one with the lowest id on ties.
+    pub fn largest(&self) -> Option<usize> {
+        (0..self.count())
+            .rev()
+            .max_by_key(|&label| self.sizes[label])
+    }
+
+    pub fn largest_size(&self) -> usize {
+        self.largest().map_or(0, |label| self.sizes[label])
+    }
+}
+
+mod test {
+    #![allow(unused_imports)]
+    use super::Components;
+    use crate::graph::{Graph, GraphDesc, NodeDesc, node::Node};
+
+    #[test]
+    fn components_test() {
+        let mut graph = Graph::example();
+        let components = Components::new(&graph);
+        assert_eq!(components.count(), 1);
+        assert_eq!(components.largest_size(), graph.alive());
+
+        // Node 9 hangs off node 4 only, the rest stays connected through 5 and 6.
+        for i in [0, 4] {
+            graph.delete(unsafe { Node::new(i) });
+        }
+
+        let components = Components::new(&graph);
+        let [n1, n7, n9] = unsafe { [1, 7, 9].map(|i| Node::new(i)) };
+
+        assert_eq!(components.count(), 2);
+        assert_eq!(components.sizes(), &[7, 1]);
+        assert_eq!(components.label(n1), components.label(n7));
+        assert_ne!(components.label(n1), components.label(n9));
+        assert_eq!(components.largest(), Some(components.label(n1)));
+    }
+}
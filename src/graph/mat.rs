@@ -3,10 +3,7 @@ use std::{
     ops::{Index, IndexMut},
 };
 
-use super::{
-    GraphDesc, NodeDesc,
-    node::{Node, NodeStatusTracker},
-};
+use super::node::Node;
 
 #[derive(Debug, Clone, Default)]
 pub struct Mat<T: Default + Clone + fmt::Display + ToString> {
@@ -16,25 +13,26 @@ pub struct Mat<T: Default + Clone + fmt::Display + ToString> {
 
 impl<T: Default + Clone + fmt::Display + ToString> Mat<T> {
     pub fn new(node_count: usize) -> Self {
+        Self::filled(node_count, <T as Default>::default())
+    }
+
+    pub fn filled(node_count: usize, value: T) -> Self {
         Self {
-            array: vec![<T as Default>::default(); node_count * node_count],
+            array: vec![value; node_count * node_count],
             node_count,
         }
     }
 
-    pub fn delete(&mut self, tracker: &NodeStatusTracker, i: Node) {
-        for j in tracker.iter_alive() {
-            if let Some(value) = self.get_mut(j, i) {
-                *value = <T as Default>::default();
+    /// Sets the row and the column of a deleted node to `value`.
+    pub fn delete(&mut self, i: Node, value: T) {
+        for j in 0..self.node_count {
+            let j = unsafe { Node::new(j as u32) };
+            if let Some(cell) = self.get_mut(j, i) {
+                *cell = value.clone();
+            }
+            if let Some(cell) = self.get_mut(i, j) {
+                *cell = value.clone();
             }
-        }
-    }
-
-    pub fn row(&self, node: Node) -> Row<'_, T> {
-        let start = self.node_count * node.as_idx();
-        Row {
-            row: &self.array[start..start + self.node_count],
-            row_id: node.as_idx(),
         }
     }
 
@@ -55,45 +53,6 @@ impl<T: Default + Clone + fmt::Display + ToString> Mat<T> {
     }
 }
 
-impl Mat<bool> {
-    pub fn set(&mut self, i: Node, j: Node) {
-        self[(i, j)] = true;
-    }
-
-    pub fn unset(&mut self, i: Node, j: Node) {
-        self[(i, j)] = false;
-    }
-
-    pub fn is_set(&self, i: Node, j: Node) -> bool {
-        self[(i, j)]
-    }
-}
-
-impl From<GraphDesc> for Mat<bool> {
-    fn from(value: GraphDesc) -> Self {
-        let count = value.node_count();
-        let mut mat = Self::new(count);
-
-        for NodeDesc {
-            node_id: i, nodes, ..
-        } in value.nodes().iter()
-        {
-            for j in nodes.iter() {
-                assert_ne!(*i, *j);
-                assert!(*i < count as u32);
-                assert!(*j < count as u32);
-
-                let (i, j) = unsafe { (Node::new(*i), Node::new(*j)) };
-
-                mat.set(i, j);
-                mat.set(j, i);
-            }
-        }
-
-        mat
-    }
-}
-
 impl<T: Default + Clone + fmt::Display + ToString> fmt::Display for Mat<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for i in 0..self.node_count {
@@ -133,58 +92,6 @@ impl<T: Default + Clone + fmt::Display + ToString> IndexMut<(Node, Node)> for Ma
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct Row<'a, T> {
-    row: &'a [T],
-    row_id: usize,
-}
-
-impl<T> Row<'_, T> {
-    pub fn get(&self, i: Node) -> Option<&T> {
-        if i.as_idx() != self.row_id {
-            Some(&self.row[i.as_idx()])
-        } else {
-            None
-        }
-    }
-
-    pub fn iter(&self) -> RowIter<'_, T> {
-        RowIter {
-            iter: self.row.iter(),
-            row_id: self.row_id as u32,
-            current_column: 0,
-        }
-    }
-}
-
-impl<T> Index<Node> for Row<'_, T> {
-    type Output = T;
-
-    fn index(&self, i: Node) -> &Self::Output {
-        self.get(i)
-            .expect("You cannot access diagonal line of matrix.")
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct RowIter<'a, T> {
-    iter: std::slice::Iter<'a, T>,
-    row_id: u32,
-    current_column: u32,
-}
-
-impl<'a, T> Iterator for RowIter<'a, T> {
-    type Item = &'a T;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.row_id != self.current_column {
-            self.iter.next()
-        } else {
-            None
-        }
-    }
-}
-
 mod test {
     #![allow(unused_imports)]
     use super::Mat;
@@ -223,7 +130,7 @@ mod test {
             let deleted = &[Node::new(2), Node::new(4)];
             for &node in deleted.iter() {
                 tracker.delete(node);
-                mat_i32.delete(&tracker, node);
+                mat_i32.delete(node, -1);
             }
 
             assert_eq!(
@@ -232,8 +139,10 @@ mod test {
             );
 
             for node in tracker.iter_alive() {
-                assert_eq!(mat_i32[(node, deleted[0])], 0);
-                assert_eq!(mat_i32[(node, deleted[1])], 0);
+                for &deleted in deleted.iter() {
+                    assert_eq!(mat_i32[(node, deleted)], -1);
+                    assert_eq!(mat_i32[(deleted, node)], -1);
+                }
             }
         }
     }
@@ -1,12 +1,23 @@
-use mat::Mat;
+use adjacency::{Adjacency, Edge};
+use components::Components;
 use node::{NADVec, Node, NodeStatusTracker};
 use path_finder::PathFinder;
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use validate::DescError;
 
+use crate::config::{AttackDesc, BetweennessMode, CapacityMode};
+
+pub mod adjacency;
+pub mod components;
 pub mod mat;
 pub mod node;
 pub mod path_finder;
+pub mod validate;
 
 #[derive(Clone, Debug)]
 pub struct GraphInfo {
@@ -14,12 +25,15 @@ pub struct GraphInfo {
     pub zmax: f64,
     pub beta: f64,
     pub beta_delta: f64,
+    pub efficiency: f64,
+    pub relative_efficiency: f64,
     pub capacity: NADVec<f64>,
     pub min_capacity: Node,
     pub max_capacity: Node,
     pub betweenness: NADVec<f64>,
     pub min_betweenness: Node,
     pub max_betweenness: Node,
+    pub components: Components,
 }
 
 impl GraphInfo {
@@ -31,10 +45,13 @@ impl GraphInfo {
             zmax: 0.0,
             beta: 0.0,
             beta_delta: 0.0,
+            efficiency: 0.0,
+            relative_efficiency: 0.0,
             min_capacity: Node::default(),
             max_capacity: Node::default(),
             min_betweenness: Node::default(),
             max_betweenness: Node::default(),
+            components: Components::default(),
         }
     }
 }
@@ -42,15 +59,19 @@ impl GraphInfo {
 #[derive(Clone, Debug, Default)]
 pub struct Graph {
     pub tracker: NodeStatusTracker,
-    pub adjacency: Mat<bool>,
-    pub path_finder: PathFinder,
+    pub adjacency: Adjacency,
+    /// See [`Self::paths`].
+    path_finder: Option<PathFinder>,
 }
 
 impl Graph {
-    pub const CONNECTION_COST: i32 = 2;
+    /// Weight of edges listed by plain node id in the graph file.
+    pub const DEFAULT_WEIGHT: f64 = 1.0;
 
     pub fn example() -> Self {
-        GraphDesc::example().into()
+        GraphDesc::example()
+            .try_into()
+            .expect("The example graph is valid")
     }
 
     pub fn node_count(&self) -> usize {
@@ -61,62 +82,295 @@ impl Graph {
         self.tracker.alive()
     }
 
+    /// Whether edges are one-way arcs `i -> j` as listed in the graph file.
+    pub fn is_directed(&self) -> bool {
+        self.adjacency.is_directed()
+    }
+
+    /// Whether there is an edge from `i` to `j`. For undirected graphs this is symmetric.
     pub fn is_adjacent(&self, i: Node, j: Node) -> bool {
-        self.adjacency[(i, j)]
+        self.adjacency.is_adjacent(i, j)
+    }
+
+    pub fn weight(&self, i: Node, j: Node) -> Option<f64> {
+        self.adjacency.weight(i, j)
+    }
+
+    /// Alive nodes reachable from `node` over a single edge, with the edge weights.
+    pub fn neighbours(&self, node: Node) -> impl Iterator<Item = Edge> {
+        self.adjacency.neighbours(node).iter().copied()
     }
 
-    pub fn update_paths(&mut self) -> Option<()> {
-        for src in self.tracker.iter_alive() {
-            self.path_finder
-                .find_shortest_path_for(&self.tracker, &self.adjacency, src)?;
+    /// Alive nodes with an edge into `node`, with the edge weights.
+    pub fn predecessors(&self, node: Node) -> impl Iterator<Item = Edge> {
+        self.adjacency.predecessors(node).iter().copied()
+    }
+
+    /// Number of edges touching `node`, arcs in both directions for directed graphs.
+    pub fn degree(&self, node: Node) -> usize {
+        let outgoing = self.adjacency.neighbours(node).len();
+        if self.is_directed() {
+            outgoing + self.adjacency.predecessors(node).len()
+        } else {
+            outgoing
+        }
+    }
+
+    /// Shortest paths between the alive nodes. `None` until [`Self::update_paths`], after
+    /// a node was deleted, and in graphs whose paths went to a [`Self::split_off`] copy.
+    pub fn paths(&self) -> Option<&PathFinder> {
+        self.path_finder.as_ref().filter(|paths| !paths.is_stale())
+    }
+
+    pub fn update_paths(&mut self) {
+        let node_count = self.node_count();
+        self.path_finder
+            .get_or_insert_with(|| PathFinder::new(node_count))
+            .update(&self.tracker, &self.adjacency);
+    }
+
+    /// Adds the current shortest paths to the memberships of the legacy betweenness, see
+    /// [`PathFinder::record_memberships`]. `None` if the paths are stale or a path could
+    /// not be reconstructed.
+    pub fn record_path_memberships(&mut self) -> Option<()> {
+        self.path_finder
+            .as_mut()
+            .filter(|paths| !paths.is_stale())?
+            .record_memberships(&self.tracker, &self.adjacency)
+    }
+
+    /// A copy of the graph taking over its shortest paths, so only one of them holds
+    /// the O(n²) tables.
+    pub fn split_off(&mut self) -> Self {
+        Self {
+            tracker: self.tracker.clone(),
+            adjacency: self.adjacency.clone(),
+            path_finder: self.path_finder.take(),
         }
-        Some(())
     }
 
     pub fn delete(&mut self, node: Node) {
         self.tracker.delete(node);
-        self.adjacency.delete(&self.tracker, node);
+        self.adjacency.delete(node);
+        if let Some(paths) = self.path_finder.as_mut() {
+            paths.delete(node);
+        }
     }
 }
 
 impl fmt::Display for Graph {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "tracker: {:?}", self.tracker)?;
+        writeln!(f, "directed: {}", self.is_directed())?;
         writeln!(f, "adjacency:\n{}", self.adjacency)?;
-        writeln!(f, "path_finder:\n{}", self.path_finder)
+        match self.path_finder.as_ref() {
+            Some(path_finder) => writeln!(f, "path_finder:\n{path_finder}"),
+            None => writeln!(f, "path_finder: none"),
+        }
     }
 }
 
-impl From<GraphDesc> for Graph {
-    fn from(value: GraphDesc) -> Self {
+/// Fails with every problem of the description, see [`GraphDesc::validate`].
+impl TryFrom<GraphDesc> for Graph {
+    type Error = Vec<DescError>;
+
+    fn try_from(value: GraphDesc) -> Result<Self, Self::Error> {
         let node_count = value.node_count();
 
+        value.validate()?;
         let tracker = NodeStatusTracker::new(node_count);
-        let path_finder = PathFinder::new(node_count);
-        let adjacency = Mat::<bool>::from(value);
+        let adjacency = Adjacency::from_valid(&value);
 
-        Self {
+        Ok(Self {
             tracker,
             adjacency,
-            path_finder,
+            path_finder: None,
+        })
+    }
+}
+
+/// A neighbour in the graph file, either a plain node id (`3`) connected with
+/// [`Graph::DEFAULT_WEIGHT`] or a table with an explicit weight (`{ id = 3, weight = 1.7 }`).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[serde(untagged)]
+pub enum NeighbourDesc {
+    Id(u32),
+    Weighted { id: u32, weight: f64 },
+}
+
+impl NeighbourDesc {
+    pub fn new(id: u32, weight: f64) -> Self {
+        if weight == Graph::DEFAULT_WEIGHT {
+            Self::Id(id)
+        } else {
+            Self::Weighted { id, weight }
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        match *self {
+            Self::Id(id) | Self::Weighted { id, .. } => id,
+        }
+    }
+
+    pub fn weight(&self) -> f64 {
+        match *self {
+            Self::Id(_) => Graph::DEFAULT_WEIGHT,
+            Self::Weighted { weight, .. } => weight,
         }
     }
 }
 
+impl From<u32> for NeighbourDesc {
+    fn from(value: u32) -> Self {
+        Self::Id(value)
+    }
+}
+
 #[derive(Default, Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
 pub struct NodeDesc {
     pub node_id: u32,
     pub position: [f32; 3],
-    pub nodes: Vec<u32>,
+    pub nodes: Vec<NeighbourDesc>,
 }
 
 #[derive(Default, Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
 pub struct GraphDesc {
     pub alpha: f64,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub directed: bool,
+    #[serde(default, skip_serializing_if = "BetweennessMode::is_default")]
+    pub betweenness: BetweennessMode,
+    #[serde(default, skip_serializing_if = "CapacityMode::is_default")]
+    pub capacity: CapacityMode,
+    #[serde(default, skip_serializing_if = "AttackDesc::is_default")]
+    pub attack: AttackDesc,
+    /// Name of the pipeline to run, the built-in one when missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pipeline: Option<String>,
     pub nodes: Vec<NodeDesc>,
 }
 
+/// Why a graph file could not be loaded.
+#[derive(Debug)]
+pub enum LoadError {
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        error: toml::de::Error,
+    },
+    Invalid {
+        path: PathBuf,
+        errors: Vec<DescError>,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, error } => write!(f, "failed to read {}: {error}", path.display()),
+            Self::Parse { path, error } => {
+                write!(f, "failed to parse {}: {}", path.display(), error.message())
+            }
+            Self::Invalid { path, errors } => {
+                write!(f, "invalid graph in {}:", path.display())?;
+                errors.iter().try_for_each(|error| write!(f, "\n  {error}"))
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// Why a graph file could not be saved, the previous file is left untouched.
+#[derive(Debug)]
+pub enum SaveError {
+    Serialize(toml::ser::Error),
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Serialize(error) => write!(f, "failed to serialize the graph: {error}"),
+            Self::Io { path, error } => write!(f, "failed to write {}: {error}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
 impl GraphDesc {
+    /// Reads and validates a graph file.
+    pub fn load(path: &Path) -> Result<Self, LoadError> {
+        let desc = std::fs::read_to_string(path).map_err(|error| LoadError::Io {
+            path: path.to_owned(),
+            error,
+        })?;
+
+        let desc: Self = toml::from_str(&desc).map_err(|error| LoadError::Parse {
+            path: path.to_owned(),
+            error,
+        })?;
+
+        desc.validate().map_err(|errors| LoadError::Invalid {
+            path: path.to_owned(),
+            errors,
+        })?;
+
+        Ok(desc)
+    }
+
+    /// Writes the graph next to `path` and renames it over it, so a failed save never
+    /// leaves a truncated file. The temporary file is named after the process and the
+    /// save, so concurrent saves don't share it, and is removed if the save fails. The
+    /// file being replaced is kept as `<path>.bak`.
+    pub fn save(&self, path: &Path) -> Result<(), SaveError> {
+        static SAVES: AtomicUsize = AtomicUsize::new(0);
+
+        let contents = toml::to_string(self).map_err(SaveError::Serialize)?;
+        let io = |path: &Path| {
+            let path = path.to_owned();
+            move |error| SaveError::Io { path, error }
+        };
+
+        let save = SAVES.fetch_add(1, Ordering::Relaxed);
+        let tmp = Self::sibling(path, &format!("{}.{save}.tmp", std::process::id()));
+        let mut file = std::fs::File::create_new(&tmp).map_err(io(&tmp))?;
+
+        let result = std::io::Write::write_all(&mut file, contents.as_bytes())
+            .and_then(|()| file.sync_all())
+            .map_err(io(&tmp))
+            .and_then(|()| {
+                if path.exists() {
+                    let bak = Self::sibling(path, "bak");
+                    std::fs::copy(path, &bak).map_err(io(&bak))?;
+                }
+
+                std::fs::rename(&tmp, path).map_err(io(path))
+            });
+
+        if result.is_err() {
+            drop(file);
+            let _ = std::fs::remove_file(&tmp);
+        }
+        result
+    }
+
+    /// `path` with `extension` appended, `graph.toml` becomes `graph.toml.bak`.
+    fn sibling(path: &Path, extension: &str) -> PathBuf {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".");
+        name.push(extension);
+        name.into()
+    }
+
     pub fn nodes(&self) -> &[NodeDesc] {
         self.nodes.as_slice()
     }
@@ -128,41 +382,46 @@ impl GraphDesc {
     pub fn example() -> Self {
         Self {
             alpha: 3.0,
+            directed: false,
+            capacity: CapacityMode::default(),
+            attack: AttackDesc::default(),
+            pipeline: None,
+            betweenness: BetweennessMode::default(),
             nodes: vec![
                 NodeDesc {
                     node_id: 0,
                     position: [0.0, 0.0, 0.0],
-                    nodes: vec![1, 2, 3],
+                    nodes: vec![1.into(), 2.into(), 3.into()],
                 },
                 NodeDesc {
                     node_id: 1,
                     position: [1.0, 0.0, 0.0],
-                    nodes: vec![4, 5],
+                    nodes: vec![4.into(), 5.into()],
                 },
                 NodeDesc {
                     node_id: 2,
                     position: [2.0, 0.0, 0.0],
-                    nodes: vec![4, 6],
+                    nodes: vec![4.into(), 6.into()],
                 },
                 NodeDesc {
                     node_id: 3,
                     position: [3.0, 0.0, 0.0],
-                    nodes: vec![5, 6],
+                    nodes: vec![5.into(), 6.into()],
                 },
                 NodeDesc {
                     node_id: 4,
                     position: [0.0, 1.0, 0.0],
-                    nodes: vec![9],
+                    nodes: vec![9.into()],
                 },
                 NodeDesc {
                     node_id: 5,
                     position: [1.0, 1.0, 0.0],
-                    nodes: vec![8],
+                    nodes: vec![8.into()],
                 },
                 NodeDesc {
                     node_id: 6,
                     position: [2.0, 1.0, 0.0],
-                    nodes: vec![7],
+                    nodes: vec![7.into()],
                 },
                 NodeDesc {
                     node_id: 7,
@@ -186,23 +445,176 @@ impl GraphDesc {
 
 mod test {
     #![allow(unused_imports)]
-    use super::{Graph, GraphDesc};
-    use crate::graph::{NodeDesc, node::Node};
+    use super::{Graph, GraphDesc, SaveError};
+    use crate::{
+        config::{AttackDesc, AttackStrategy, Ranking},
+        graph::{NodeDesc, node::Node},
+    };
 
     #[test]
     fn test_graph_desc() {
         let desc = GraphDesc::example();
-        let graph = Graph::from(desc.clone());
+        let graph = Graph::try_from(desc.clone()).unwrap();
+        for NodeDesc {
+            node_id: i, nodes, ..
+        } in desc.nodes().iter()
+        {
+            for j in nodes.iter() {
+                let (i, j) = unsafe { (Node::new(*i), Node::new(j.id())) };
+
+                assert!(graph.is_adjacent(i, j));
+                assert!(graph.is_adjacent(j, i));
+            }
+        }
+    }
+
+    #[test]
+    fn test_directed_desc() {
+        let desc = GraphDesc {
+            directed: true,
+            ..GraphDesc::example()
+        };
+        let mut graph = Graph::try_from(desc.clone()).unwrap();
         for NodeDesc {
             node_id: i, nodes, ..
         } in desc.nodes().iter()
         {
             for j in nodes.iter() {
-                let (i, j) = unsafe { (Node::new(*i), Node::new(*j)) };
+                let (i, j) = unsafe { (Node::new(*i), Node::new(j.id())) };
 
-                assert!(graph.adjacency.is_set(i, j));
-                assert!(graph.adjacency.is_set(j, i));
+                assert!(graph.is_adjacent(i, j));
+                assert!(!graph.is_adjacent(j, i));
             }
         }
+
+        // The example only has arcs pointing away from node 0.
+        graph.update_paths();
+        let [a, b] = unsafe { [Node::new(0), Node::new(9)] };
+        assert_eq!(
+            graph.paths().unwrap().cost(a, b),
+            3.0 * Graph::DEFAULT_WEIGHT
+        );
+        assert_eq!(graph.paths().unwrap().cost(b, a), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_weighted_desc() {
+        let desc: GraphDesc = toml::from_str(
+            r#"
+            alpha = 0.5
+
+            [[nodes]]
+            node_id = 0
+            position = [0.0, 0.0, 0.0]
+            nodes = [1, { id = 2, weight = 5.0 }]
+
+            [[nodes]]
+            node_id = 1
+            position = [1.0, 0.0, 0.0]
+            nodes = [{ id = 2, weight = 1.5 }]
+
+            [[nodes]]
+            node_id = 2
+            position = [2.0, 0.0, 0.0]
+            nodes = []
+            "#,
+        )
+        .unwrap();
+
+        let mut graph = Graph::try_from(desc.clone()).unwrap();
+        graph.update_paths();
+
+        let [a, b, c] = unsafe { [0, 1, 2].map(|i| Node::new(i)) };
+        assert_eq!(graph.weight(a, b), Some(Graph::DEFAULT_WEIGHT));
+        assert_eq!(graph.weight(c, a), Some(5.0));
+        assert_eq!(
+            graph.paths().unwrap().cost(a, c),
+            Graph::DEFAULT_WEIGHT + 1.5
+        );
+        assert!(graph.paths().unwrap().contains((a, c), b));
+
+        let saved = toml::to_string(&desc).unwrap();
+        assert_eq!(toml::from_str::<GraphDesc>(saved.as_str()).unwrap(), desc);
+    }
+
+    #[test]
+    fn test_attack_desc() {
+        let desc: GraphDesc = toml::from_str(
+            r#"
+            alpha = 0.5
+
+            [attack]
+            strategy = "targets"
+            ranking = "initial"
+            targets = [2, 0]
+
+            [[nodes]]
+            node_id = 0
+            position = [0.0, 0.0, 0.0]
+            nodes = []
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            desc.attack,
+            AttackDesc {
+                strategy: AttackStrategy::Targets,
+                ranking: Ranking::Initial,
+                targets: vec![2, 0],
+                ..Default::default()
+            }
+        );
+
+        let saved = toml::to_string(&desc).unwrap();
+        assert_eq!(toml::from_str::<GraphDesc>(saved.as_str()).unwrap(), desc);
+        assert!(
+            !toml::to_string(&GraphDesc::example())
+                .unwrap()
+                .contains("attack")
+        );
+    }
+
+    #[test]
+    fn test_save_desc() {
+        let dir = std::env::temp_dir().join(format!("graph-save-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("graph.toml");
+        let bak = dir.join("graph.toml.bak");
+
+        let first = GraphDesc::example();
+        first.save(&path).unwrap();
+        assert_eq!(GraphDesc::load(&path).unwrap(), first);
+        assert!(!bak.exists());
+
+        let second = GraphDesc {
+            alpha: 0.5,
+            ..GraphDesc::example()
+        };
+        second.save(&path).unwrap();
+        assert_eq!(GraphDesc::load(&path).unwrap(), second);
+        assert_eq!(GraphDesc::load(&bak).unwrap(), first);
+
+        // A save into a missing directory fails without touching anything.
+        assert!(matches!(
+            second.save(&dir.join("missing").join("graph.toml")),
+            Err(SaveError::Io { .. })
+        ));
+
+        // A save over a directory fails after writing the temporary file, and removes it.
+        std::fs::create_dir(dir.join("graph")).unwrap();
+        assert!(matches!(
+            second.save(&dir.join("graph")),
+            Err(SaveError::Io { .. })
+        ));
+
+        let mut files: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        files.sort();
+        assert_eq!(files, ["graph", "graph.toml", "graph.toml.bak"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
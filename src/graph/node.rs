@@ -10,6 +10,10 @@ impl Default for Node {
 }
 
 impl Node {
+    /// # Safety
+    ///
+    /// `idx` must be the index of a node of the graph the node is used with. Nothing
+    /// checks it, node data is indexed with it directly.
     pub unsafe fn new(idx: u32) -> Self {
         Self(idx)
     }
@@ -99,7 +103,7 @@ impl Iterator for AliveNodeIter<'_> {
 }
 
 /// Node-associated data vector
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct NADVec<T: Default + Clone>(Vec<T>);
 
 impl<T: Default + Clone> NADVec<T> {
@@ -111,6 +115,10 @@ impl<T: Default + Clone> NADVec<T> {
         self.0.fill(value);
     }
 
+    /// # Safety
+    ///
+    /// The vector has an entry for every node, dead ones included, and is indexed by
+    /// [`Node::as_idx`] rather than by the node.
     pub unsafe fn clone_vec(&self) -> Vec<T> {
         self.0.clone()
     }
@@ -1,136 +1,270 @@
-use crate::graph::Graph;
-
-use std::fmt;
+use std::{cmp::Ordering, collections::BinaryHeap, fmt};
 
 use super::{
+    adjacency::{Adjacency, Edge},
     mat::Mat,
     node::{Node, NodeStatusTracker},
 };
 
+/// All-pairs shortest path distances together with the number of distinct shortest
+/// paths for every pair. Path membership is derived from the distances, so the
+/// storage stays O(n²), unless the legacy betweenness records its memberships on the
+/// small graphs it accepts.
 #[derive(Clone, Debug, Default)]
 pub struct PathFinder {
-    costs: Mat<i32>,
-    paths: Vec<Mat<bool>>,
+    costs: Mat<f64>,
+    counts: Mat<f64>,
+    /// For every node, the pairs one of whose reconstructed paths went through it, see
+    /// [`Self::record_memberships`]. Empty until the first record.
+    memberships: Vec<Mat<bool>>,
+    /// Whether nodes were deleted since the last [`Self::update`]. The paths between
+    /// the remaining nodes may have gone through them.
+    stale: bool,
+}
+
+/// Whether two path lengths are equal up to the rounding error accumulated while
+/// summing edge weights.
+pub fn same_cost(a: f64, b: f64) -> bool {
+    const EPSILON: f64 = 1e-9;
+    if a.is_infinite() || b.is_infinite() {
+        return a == b;
+    }
+
+    (a - b).abs() <= EPSILON * a.abs().max(b.abs()).max(1.0)
 }
 
 impl PathFinder {
+    /// Tables without any path, stale until the first [`Self::update`].
     pub fn new(node_count: usize) -> Self {
         Self {
-            costs: Mat::new(node_count),
-            paths: vec![Mat::new(node_count); node_count],
+            costs: Mat::filled(node_count, f64::INFINITY),
+            counts: Mat::new(node_count),
+            memberships: vec![],
+            stale: true,
+        }
+    }
+
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// Computes the paths between every pair of alive nodes.
+    pub fn update(&mut self, tracker: &NodeStatusTracker, adj: &Adjacency) {
+        for src in tracker.iter_alive() {
+            self.find_shortest_path_for(tracker, adj, src);
         }
+        self.stale = false;
+    }
+
+    pub fn cost(&self, i: Node, j: Node) -> f64 {
+        if i == j { 0.0 } else { self.costs[(i, j)] }
     }
 
-    pub fn cost(&self, i: Node, j: Node) -> i32 {
-        self.costs[(i, j)]
+    /// Number of distinct shortest paths between `i` and `j`.
+    pub fn count(&self, i: Node, j: Node) -> f64 {
+        if i == j { 1.0 } else { self.counts[(i, j)] }
+    }
+
+    /// Whether `node` lies on at least one shortest path between the endpoints of
+    /// `path`. Endpoints are considered to lie on their own paths.
+    pub fn contains(&self, (s, t): (Node, Node), node: Node) -> bool {
+        let (to_node, from_node, total) = (self.cost(s, node), self.cost(node, t), self.cost(s, t));
+        if to_node.is_infinite() || from_node.is_infinite() {
+            return false;
+        }
+
+        same_cost(to_node + from_node, total)
     }
 
-    pub fn contains(&self, path: (Node, Node), node: Node) -> bool {
-        self.paths[node.as_idx()][path]
+    /// Whether `node` was on a reconstructed path between the endpoints of `path`, in
+    /// either direction, at any [`Self::record_memberships`] so far.
+    pub fn is_member(&self, path: (Node, Node), node: Node) -> bool {
+        self.memberships
+            .get(node.as_idx())
+            .and_then(|pairs| pairs.get(path.0, path.1))
+            .is_some_and(|&member| member)
     }
 
-    pub fn find_shortest_path_for(
+    /// Marks the nodes of one reconstructed path for every ordered pair of alive nodes.
+    /// The marks of the previous calls are kept, deleted nodes and paths that changed
+    /// included, as the original betweenness count did. `None` if a path could not be
+    /// reconstructed from the current tables.
+    pub fn record_memberships(
         &mut self,
         tracker: &NodeStatusTracker,
-        adj: &Mat<bool>,
-        src: Node,
+        adj: &Adjacency,
     ) -> Option<()> {
-        let mut spt = vec![false; tracker.node_count()];
-        let mut dist = vec![i32::MAX; tracker.node_count()];
-
-        dist[src.as_idx()] = 0;
+        if self.memberships.is_empty() {
+            self.memberships = vec![Mat::new(tracker.node_count()); tracker.node_count()];
+        }
 
-        let min_dist = |spt: &[bool], dist: &[i32]| {
-            let mut min_node = Node::default();
-            let mut min_path = i32::MAX;
+        for src in tracker.iter_alive() {
+            for target in tracker.iter_alive().exclude(src) {
+                if self.cost(src, target).is_infinite() {
+                    continue;
+                }
 
-            for node in tracker.iter_alive() {
-                let idx = node.as_idx();
-                if !spt[idx] && dist[idx] < min_path {
-                    min_path = dist[idx];
-                    min_node = node;
+                for node in self.reconstruct_path(tracker, adj, src, target)? {
+                    let pairs = &mut self.memberships[node.as_idx()];
+                    pairs[(src, target)] = true;
+                    pairs[(target, src)] = true;
                 }
             }
+        }
 
-            if min_node.is_valid() {
-                Some(min_node)
-            } else {
-                None
-            }
-        };
+        Some(())
+    }
 
-        for _ in 0..tracker.alive() {
-            let min_node = min_dist(&spt, &dist)?;
-            let min_idx = min_node.as_idx();
-            assert!(min_node.is_valid());
+    /// Forgets the paths from and to a deleted node and marks the others stale.
+    pub fn delete(&mut self, node: Node) {
+        self.costs.delete(node, f64::INFINITY);
+        self.counts.delete(node, 0.0);
+        self.stale = true;
+    }
 
-            spt[min_idx] = true;
+    /// Computes the distances from `src` to every alive node. Nodes that cannot be
+    /// reached, e.g. in another component, get an infinite cost and no paths.
+    fn find_shortest_path_for(&mut self, tracker: &NodeStatusTracker, adj: &Adjacency, src: Node) {
+        let mut spt = vec![false; tracker.node_count()];
+        let mut dist = vec![f64::INFINITY; tracker.node_count()];
+        let mut sigma = vec![0.0; tracker.node_count()];
 
-            for j in tracker.iter_alive() {
-                if !spt[j.as_idx()]
-                    && adj[(min_node, j)]
-                    && dist[min_idx] != i32::MAX
-                    && dist[min_idx] + Graph::CONNECTION_COST < dist[j.as_idx()]
-                {
-                    dist[j.as_idx()] = dist[min_idx] + Graph::CONNECTION_COST;
-                }
-            }
-        }
+        dist[src.as_idx()] = 0.0;
+        sigma[src.as_idx()] = 1.0;
+
+        let mut queue = BinaryHeap::from([Candidate(0.0, src)]);
 
-        for target in tracker.iter_alive() {
-            if target == src {
+        while let Some(Candidate(cost, min_node)) = queue.pop() {
+            let min_idx = min_node.as_idx();
+            if spt[min_idx] || cost > dist[min_idx] {
                 continue;
             }
 
-            let path = Self::reconstruct_path(tracker, adj, dist.as_slice(), src, target);
-            for node in path.iter() {
-                self.paths[node.as_idx()].set(src, target);
-                self.paths[node.as_idx()].set(target, src);
+            spt[min_idx] = true;
+
+            for &Edge { node: j, weight } in adj.neighbours(min_node) {
+                if spt[j.as_idx()] {
+                    continue;
+                }
+
+                let alt = dist[min_idx] + weight;
+                if same_cost(alt, dist[j.as_idx()]) {
+                    sigma[j.as_idx()] += sigma[min_idx];
+                } else if alt < dist[j.as_idx()] {
+                    dist[j.as_idx()] = alt;
+                    sigma[j.as_idx()] = sigma[min_idx];
+                    queue.push(Candidate(alt, j));
+                }
             }
         }
 
         for node in tracker.iter_alive().exclude(src) {
             self.costs[(src, node)] = dist[node.as_idx()];
+            self.counts[(src, node)] = sigma[node.as_idx()];
         }
-
-        Some(())
     }
 
-    fn reconstruct_path(
+    /// Walks back from `target` to `src` along one shortest path. Each pass scans the
+    /// alive nodes by index and steps to every predecessor on a shortest path it meets,
+    /// continuing the scan from there, which is the tie-breaking of the original
+    /// implementation. The returned path starts at `target`, `None` if the tables do
+    /// not match the graph.
+    pub fn reconstruct_path(
+        &self,
         tracker: &NodeStatusTracker,
-        adj: &Mat<bool>,
-        dist: &[i32],
+        adj: &Adjacency,
         src: Node,
         mut target: Node,
-    ) -> Vec<Node> {
+    ) -> Option<Vec<Node>> {
         let mut path = vec![target];
-
-        let mut weight = dist[target.as_idx()];
+        let mut cost = self.cost(src, target);
 
         while target != src {
+            let mut stepped = false;
             for node in tracker.iter_alive().exclude(target) {
-                if adj[(node, target)] {
-                    let diff = weight - Graph::CONNECTION_COST;
-                    if diff == dist[node.as_idx()] {
-                        weight = diff;
-                        target = node;
-                        path.push(target);
-                    }
+                let Some(weight) = adj.weight(node, target) else {
+                    continue;
+                };
+
+                if same_cost(self.cost(src, node) + weight, cost) {
+                    cost = self.cost(src, node);
+                    target = node;
+                    path.push(target);
+                    stepped = true;
                 }
             }
+
+            if !stepped {
+                return None;
+            }
         }
 
-        path
+        Some(path)
+    }
+}
+
+/// Dijkstra queue entry, ordered so that `BinaryHeap` pops the closest node first.
+#[derive(PartialEq)]
+struct Candidate(f64, Node);
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .0
+            .total_cmp(&self.0)
+            .then_with(|| other.1.cmp(&self.1))
     }
 }
 
 impl fmt::Display for PathFinder {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "costs:\n{}", self.costs)?;
-        for (i, path) in self.paths.iter().enumerate() {
-            writeln!(f, "node {i}:\n{path}")?;
-        }
+        writeln!(f, "counts:\n{}", self.counts)
+    }
+}
+
+mod test {
+    #![allow(unused_imports)]
+    use crate::graph::{Graph, GraphDesc, NodeDesc, node::Node};
+
+    #[test]
+    fn ring_paths() {
+        let desc = GraphDesc {
+            nodes: (0..4)
+                .map(|i| NodeDesc {
+                    node_id: i,
+                    nodes: vec![((i + 1) % 4).into()],
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let mut graph = Graph::try_from(desc).unwrap();
+        assert!(graph.paths().is_none());
+        graph.update_paths();
+
+        let [a, b, c, d] = unsafe { [0, 1, 2, 3].map(|i| Node::new(i)) };
+        let finder = graph.paths().unwrap();
+
+        assert_eq!(finder.cost(a, c), 2.0 * Graph::DEFAULT_WEIGHT);
+        assert_eq!(finder.count(a, c), 2.0);
+        assert_eq!(finder.count(a, b), 1.0);
+
+        assert!(finder.contains((a, c), b));
+        assert!(finder.contains((a, c), d));
+        assert!(finder.contains((a, c), a));
+        assert!(!finder.contains((a, b), c));
 
-        Ok(())
+        let path = finder.reconstruct_path(&graph.tracker, &graph.adjacency, a, c);
+        assert_eq!(path, Some(vec![c, b, a]));
     }
 }
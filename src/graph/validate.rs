@@ -0,0 +1,324 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use super::{GraphDesc, NodeDesc};
+
+/// A problem of a graph file, nodes by their `node_id`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DescError {
+    /// Alpha is negative or not a number.
+    InvalidAlpha(f64),
+    /// More than one node has the id.
+    DuplicateId(u32),
+    /// No node has the id, the `n` nodes of a file have the ids `0..n`.
+    MissingId(u32),
+    /// The node at `index` of the list has an id of `n` or more.
+    IdOutOfRange {
+        index: usize,
+        node: u32,
+    },
+    /// The node at `index` of the list has another id. Positions are handed out in list
+    /// order while edges go by id, so the nodes have to be listed by id.
+    Unordered {
+        index: usize,
+        node: u32,
+    },
+    InvalidPosition {
+        node: u32,
+        position: [f32; 3],
+    },
+    SelfLoop(u32),
+    /// `neighbour` is not the id of any node.
+    DanglingNeighbour {
+        node: u32,
+        neighbour: u32,
+    },
+    InvalidWeight {
+        node: u32,
+        neighbour: u32,
+        weight: f64,
+    },
+    /// An edge listed twice with different weights, e.g. by both of its nodes in an
+    /// undirected graph. Only the last weight would be kept.
+    AsymmetricWeight {
+        node: u32,
+        neighbour: u32,
+        weight: f64,
+        other: f64,
+    },
+}
+
+impl DescError {
+    /// The node the problem is about, `None` for problems of the whole graph.
+    pub fn node(&self) -> Option<u32> {
+        match *self {
+            Self::InvalidAlpha(_) => None,
+            Self::DuplicateId(node)
+            | Self::MissingId(node)
+            | Self::SelfLoop(node)
+            | Self::IdOutOfRange { node, .. }
+            | Self::Unordered { node, .. }
+            | Self::InvalidPosition { node, .. }
+            | Self::DanglingNeighbour { node, .. }
+            | Self::InvalidWeight { node, .. }
+            | Self::AsymmetricWeight { node, .. } => Some(node),
+        }
+    }
+}
+
+impl fmt::Display for DescError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidAlpha(alpha) => {
+                write!(f, "alpha must be a non-negative number, got {alpha}")
+            }
+            Self::DuplicateId(node) => write!(f, "node {node} is listed more than once"),
+            Self::MissingId(node) => write!(f, "node {node} is missing"),
+            Self::IdOutOfRange { index, node } => write!(
+                f,
+                "node {node} at index {index} is out of range, the ids of n nodes are 0 to n - 1"
+            ),
+            Self::Unordered { index, node } => {
+                write!(
+                    f,
+                    "node {node} is listed at index {index}, nodes must be listed by id"
+                )
+            }
+            Self::InvalidPosition { node, position } => {
+                write!(f, "node {node} has the invalid position {position:?}")
+            }
+            Self::SelfLoop(node) => write!(f, "node {node} lists itself as a neighbour"),
+            Self::DanglingNeighbour { node, neighbour } => {
+                write!(f, "node {node} lists the missing node {neighbour}")
+            }
+            Self::InvalidWeight {
+                node,
+                neighbour,
+                weight,
+            } => write!(
+                f,
+                "node {node} has an edge to {neighbour} of weight {weight}, weights must be positive"
+            ),
+            Self::AsymmetricWeight {
+                node,
+                neighbour,
+                weight,
+                other,
+            } => write!(
+                f,
+                "node {node} has an edge to {neighbour} of weight {weight}, listed before with {other}"
+            ),
+        }
+    }
+}
+
+impl GraphDesc {
+    /// Every problem that would make the graph fail to build or differ from the file,
+    /// in list order. An edge of an undirected graph may be listed by one of its nodes
+    /// or by both, so a neighbour that does not list the node back is not a problem.
+    pub fn validate(&self) -> Result<(), Vec<DescError>> {
+        let mut errors = vec![];
+
+        if self.alpha.is_nan() || self.alpha < 0.0 {
+            errors.push(DescError::InvalidAlpha(self.alpha));
+        }
+
+        let count = self.node_count();
+        let mut listed = vec![0usize; count];
+        // Listed, so neighbours naming them are not reported as dangling on top.
+        let mut out_of_range = HashSet::new();
+        for (index, &NodeDesc { node_id, .. }) in self.nodes.iter().enumerate() {
+            match listed.get_mut(node_id as usize) {
+                Some(listed) => *listed += 1,
+                None => {
+                    errors.push(DescError::IdOutOfRange {
+                        index,
+                        node: node_id,
+                    });
+                    out_of_range.insert(node_id);
+                }
+            }
+        }
+        for (id, &listed) in listed.iter().enumerate() {
+            match listed {
+                0 => errors.push(DescError::MissingId(id as u32)),
+                1 => {}
+                _ => errors.push(DescError::DuplicateId(id as u32)),
+            }
+        }
+        let ids_valid = listed.iter().all(|&listed| listed == 1);
+
+        // Edges by their ends, both orders are the same edge in an undirected graph.
+        let mut weights = HashMap::new();
+
+        for (index, desc) in self.nodes.iter().enumerate() {
+            let node = desc.node_id;
+
+            // With gaps or duplicates every following node would be out of place.
+            if ids_valid && node as usize != index {
+                errors.push(DescError::Unordered { index, node });
+            }
+
+            if !desc.position.iter().all(|x| x.is_finite()) {
+                errors.push(DescError::InvalidPosition {
+                    node,
+                    position: desc.position,
+                });
+            }
+
+            for neighbour_desc in desc.nodes.iter() {
+                let (neighbour, weight) = (neighbour_desc.id(), neighbour_desc.weight());
+
+                if neighbour == node {
+                    errors.push(DescError::SelfLoop(node));
+                    continue;
+                }
+                if out_of_range.contains(&neighbour) {
+                    continue;
+                }
+                if neighbour as usize >= count || listed[neighbour as usize] == 0 {
+                    errors.push(DescError::DanglingNeighbour { node, neighbour });
+                    continue;
+                }
+                if !(weight.is_finite() && weight > 0.0) {
+                    errors.push(DescError::InvalidWeight {
+                        node,
+                        neighbour,
+                        weight,
+                    });
+                    continue;
+                }
+
+                let key = if self.directed {
+                    (node, neighbour)
+                } else {
+                    (node.min(neighbour), node.max(neighbour))
+                };
+                match weights.insert(key, weight) {
+                    Some(other) if other != weight => errors.push(DescError::AsymmetricWeight {
+                        node,
+                        neighbour,
+                        weight,
+                        other,
+                    }),
+                    _ => {}
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+mod test {
+    #![allow(unused_imports)]
+    use super::DescError;
+    use crate::graph::{GraphDesc, NeighbourDesc};
+
+    #[test]
+    fn validate_test() {
+        assert_eq!(GraphDesc::example().validate(), Ok(()));
+
+        let mut desc = GraphDesc::example();
+        desc.alpha = -1.0;
+        desc.nodes[0].nodes.push(0.into());
+        desc.nodes[1].nodes.push(42.into());
+        desc.nodes[2].position[1] = f32::NAN;
+        desc.nodes[3].nodes.push(NeighbourDesc::new(7, 0.0));
+        // Undirected, so 1 -> 4 is the edge 4 -> 1 of weight 1.
+        desc.nodes[4].nodes.push(NeighbourDesc::new(1, 2.0));
+
+        let errors = desc.validate().unwrap_err();
+        assert_eq!(errors.len(), 6);
+        assert_eq!(errors[0], DescError::InvalidAlpha(-1.0));
+        assert!(errors[0].node().is_none());
+        assert_eq!(errors[1], DescError::SelfLoop(0));
+        assert_eq!(
+            errors[2],
+            DescError::DanglingNeighbour {
+                node: 1,
+                neighbour: 42
+            }
+        );
+        assert!(matches!(
+            errors[3],
+            DescError::InvalidPosition { node: 2, .. }
+        ));
+        assert!(matches!(
+            errors[4],
+            DescError::InvalidWeight {
+                node: 3,
+                neighbour: 7,
+                ..
+            }
+        ));
+        assert_eq!(
+            errors[5],
+            DescError::AsymmetricWeight {
+                node: 4,
+                neighbour: 1,
+                weight: 2.0,
+                other: 1.0
+            }
+        );
+
+        // The same arc both ways is fine in a directed graph.
+        let mut desc = GraphDesc {
+            directed: true,
+            ..GraphDesc::example()
+        };
+        desc.nodes[4].nodes.push(NeighbourDesc::new(1, 2.0));
+        assert_eq!(desc.validate(), Ok(()));
+
+        let mut desc = GraphDesc::example();
+        desc.nodes[5].node_id = 4;
+        assert_eq!(
+            desc.validate(),
+            Err(vec![
+                DescError::DuplicateId(4),
+                DescError::MissingId(5),
+                DescError::DanglingNeighbour {
+                    node: 1,
+                    neighbour: 5
+                },
+                DescError::DanglingNeighbour {
+                    node: 3,
+                    neighbour: 5
+                },
+            ])
+        );
+
+        // Node 9 is listed, just with an id too large for 10 nodes.
+        let mut desc = GraphDesc::example();
+        desc.nodes[9].node_id = 12;
+        desc.nodes[0].nodes.push(12.into());
+        assert_eq!(
+            desc.validate(),
+            Err(vec![
+                DescError::IdOutOfRange { index: 9, node: 12 },
+                DescError::MissingId(9),
+                DescError::DanglingNeighbour {
+                    node: 4,
+                    neighbour: 9
+                },
+            ])
+        );
+
+        let mut desc = GraphDesc::example();
+        desc.nodes.swap(8, 9);
+        let errors = desc.validate().unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                DescError::Unordered { index: 8, node: 9 },
+                DescError::Unordered { index: 9, node: 8 }
+            ]
+        );
+    }
+}
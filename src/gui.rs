@@ -0,0 +1,715 @@
+use std::{
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use log::{error, info, warn};
+use winit::{
+    event::{DeviceEvent, MouseButton, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::{
+    app::UserApp,
+    cli::Args,
+    compute::{
+        ComputeError,
+        ensemble::{Ensemble, EnsembleResult},
+        pipeline::{self, Pipeline},
+        state::State,
+        stop::StopReason,
+        sweep::{Sweep, SweepResult},
+        tree::CascadeTree,
+        worker::{ComputeEvent, ComputeWorker},
+    },
+    config::{AttackDesc, BetweennessMode, CapacityMode},
+    graph::{GraphDesc, LoadError, node::Node, validate::DescError},
+    input::{Input, Key},
+    renderer::Renderer,
+    ui::UiState,
+    world::{WorldData, camera::CameraMovement},
+};
+
+const CASCADE_TREE_FILE: &str = "cascade_tree.toml";
+const FAILURE_HISTORY_FILE: &str = "failure_history.toml";
+
+pub struct AppState {
+    /// Mirror of the state computed by `worker`, holding the iterations finished so far.
+    pub state: State,
+    pub worker: Option<ComputeWorker>,
+    /// How the last run ended, `None` while it is running.
+    pub outcome: Option<Result<StopReason, ComputeError>>,
+    pub ensemble: Option<EnsembleResult>,
+    pub sweep: Option<SweepResult>,
+    /// Ensemble or sweep computed next to the run of `worker`.
+    pub batch: Option<ComputeWorker>,
+    /// Runs the batch finished and at most how many it takes.
+    pub batch_progress: (usize, usize),
+    pub betweenness: BetweennessMode,
+    pub capacity: CapacityMode,
+    pub attack: AttackDesc,
+    /// The built-in pipeline followed by the valid ones of the pipelines file.
+    pub pipelines: Vec<Pipeline>,
+    pub pipeline: usize,
+    /// Problems of the pipelines file, the invalid pipelines are left out.
+    pub pipeline_errors: Vec<String>,
+    pub world: WorldData,
+    pub selected_node: Option<Node>,
+    /// Wave of the current iteration the view stops after, `None` shows all of them.
+    pub wave: Option<usize>,
+    /// Whether the nodes deleted so far are drawn and listed, coloured by why they failed.
+    pub show_failed: bool,
+    /// Saves the graph file on exit if it has unsaved changes.
+    pub autosave: bool,
+    /// Why the last save or revert failed.
+    pub file_error: Option<String>,
+    /// Problems of the graph file the last revert refused to load.
+    pub graph_errors: Vec<DescError>,
+    /// The graph file, Save As moves it.
+    graph_path: PathBuf,
+    /// The graph file as last loaded or saved.
+    saved: GraphDesc,
+    /// The graph as shown right after the last load or save, to tell the unsaved
+    /// changes. It differs from `saved` by the overrides of the command line.
+    unchanged: GraphDesc,
+    /// Where exports are written.
+    output: PathBuf,
+    /// Cascade tree of `state`, built when first asked for since it recomputes the
+    /// shortest paths of every wave. Dropped whenever `state` changes.
+    cascade_tree: Option<CascadeTree>,
+}
+
+impl Drop for AppState {
+    fn drop(&mut self) {
+        if !self.is_dirty() {
+            return;
+        }
+
+        if self.autosave {
+            self.save();
+        } else {
+            warn!(
+                "Discarding the unsaved changes to {}",
+                self.graph_path.display()
+            );
+        }
+    }
+}
+
+impl AppState {
+    fn new(args: &Args) -> Result<Self, LoadError> {
+        let graph_path = args.graph();
+        let saved = GraphDesc::load(&graph_path)?;
+        let desc = args.with_overrides(saved.clone());
+
+        let (pipelines, mut pipeline_errors) = Self::load_pipelines(&args.pipelines());
+        let pipeline = Self::find_pipeline(&pipelines, &desc, &mut pipeline_errors);
+
+        // The overrides of the command line are not checked by the load.
+        let state = State::initial(desc.clone()).map_err(|errors| LoadError::Invalid {
+            path: graph_path.clone(),
+            errors,
+        })?;
+        let (worker, outcome) = match pipelines[pipeline].compute(&desc) {
+            Ok(compute) => (Some(ComputeWorker::spawn(compute)), None),
+            Err(err) => (None, Some(Err(err))),
+        };
+
+        let betweenness = desc.betweenness;
+        let capacity = desc.capacity;
+        let attack = desc.attack.clone();
+        let world = WorldData::new(&state.at(0).graph.tracker, desc);
+        Ok(Self {
+            state,
+            worker,
+            outcome,
+            ensemble: None,
+            sweep: None,
+            batch: None,
+            batch_progress: (0, 0),
+            betweenness,
+            capacity,
+            attack,
+            pipelines,
+            pipeline,
+            pipeline_errors,
+            world,
+            selected_node: None,
+            wave: None,
+            show_failed: false,
+            autosave: args.autosave,
+            file_error: None,
+            graph_errors: vec![],
+            graph_path,
+            unchanged: saved.clone(),
+            saved,
+            output: args.output().to_owned(),
+            cascade_tree: None,
+        }
+        .with_unchanged())
+    }
+
+    /// Takes the graph as shown now as the one without unsaved changes.
+    fn with_unchanged(mut self) -> Self {
+        self.unchanged = self.desc();
+        self
+    }
+
+    /// Index of the pipeline named by `desc`, the built-in one if there is no such
+    /// pipeline.
+    fn find_pipeline(pipelines: &[Pipeline], desc: &GraphDesc, errors: &mut Vec<String>) -> usize {
+        let Some(name) = desc.pipeline.as_deref() else {
+            return 0;
+        };
+
+        pipelines
+            .iter()
+            .position(|p| p.name() == name)
+            .unwrap_or_else(|| {
+                let message = format!("Unknown pipeline `{name}`, running the default one");
+                error!("{message}");
+                if !errors.contains(&message) {
+                    errors.push(message);
+                }
+                0
+            })
+    }
+
+    fn load_pipelines(path: &Path) -> (Vec<Pipeline>, Vec<String>) {
+        let (pipelines, errors) = pipeline::load(path);
+        for err in errors.iter() {
+            error!("{err}");
+        }
+
+        (pipelines, errors)
+    }
+
+    /// Applies the iterations the worker finished since the last call and the results
+    /// of the batch.
+    pub fn poll(&mut self) {
+        self.poll_batch();
+
+        let Some(worker) = self.worker.as_ref() else {
+            return;
+        };
+
+        let mut finished = false;
+        for event in worker.poll() {
+            match event {
+                ComputeEvent::Progress(update) => {
+                    self.state.apply(update);
+                    self.cascade_tree = None;
+                }
+                ComputeEvent::Finished(result) => {
+                    match &result {
+                        Ok(reason) => info!(
+                            "Computed {} iterations, stopped: {reason}",
+                            self.state.iter_count()
+                        ),
+                        Err(err) => error!(
+                            "Failed to compute after {} iterations: {err}",
+                            self.state.iter_count()
+                        ),
+                    }
+                    self.outcome = Some(result);
+                    finished = true;
+                }
+                // Only sent by batches.
+                ComputeEvent::Runs { .. }
+                | ComputeEvent::EnsembleFinished(_)
+                | ComputeEvent::SweepFinished(_) => {}
+            }
+        }
+
+        if finished {
+            self.worker = None;
+        }
+    }
+
+    fn poll_batch(&mut self) {
+        let Some(batch) = self.batch.as_ref() else {
+            return;
+        };
+
+        let mut finished = false;
+        for event in batch.poll() {
+            match event {
+                ComputeEvent::Runs {
+                    finished: runs,
+                    total,
+                } => self.batch_progress = (runs, total),
+                ComputeEvent::EnsembleFinished(result) => {
+                    match result {
+                        Ok(Some(result)) => {
+                            info!(
+                                "Computed an ensemble of {} runs from seed {}",
+                                result.seeds.len(),
+                                result.seeds.first().copied().unwrap_or_default()
+                            );
+                            self.ensemble = Some(result);
+                        }
+                        Ok(None) => info!("Cancelled the ensemble"),
+                        Err(err) => error!("Failed to compute the ensemble: {err}"),
+                    }
+                    finished = true;
+                }
+                ComputeEvent::SweepFinished(result) => {
+                    match result {
+                        Ok(Some(result)) => {
+                            match result.critical_alpha {
+                                Some(alpha) => info!("Critical alpha ~ {alpha:.4}"),
+                                None => info!(
+                                    "No transition through G = {} in the swept range",
+                                    result.threshold
+                                ),
+                            }
+                            self.sweep = Some(result);
+                        }
+                        Ok(None) => info!("Cancelled the sweep"),
+                        Err(err) => error!("Failed to sweep alpha: {err}"),
+                    }
+                    finished = true;
+                }
+                // Only sent by runs.
+                ComputeEvent::Progress(_) | ComputeEvent::Finished(_) => {}
+            }
+        }
+
+        if finished {
+            self.batch = None;
+        }
+    }
+
+    pub fn cancel(&mut self) {
+        if let Some(worker) = self.worker.as_ref() {
+            worker.cancel();
+        }
+    }
+
+    /// Reruns the pipeline after a parameter (e.g. alpha) changed. The iterations of the
+    /// previous run are kept until the new ones arrive. The ensemble is dropped since
+    /// it was computed with the previous parameters, so is a running batch.
+    pub fn recompute(&mut self) {
+        let pipeline = &self.pipelines[self.pipeline];
+        match pipeline.compute(&self.desc()) {
+            Ok(compute) => {
+                self.worker = Some(ComputeWorker::spawn(compute));
+                self.outcome = None;
+            }
+            Err(err) => {
+                self.worker = None;
+                self.outcome = Some(Err(err));
+            }
+        }
+        self.ensemble = None;
+        self.batch = None;
+    }
+
+    /// The graph file with the current parameters and node positions. Nodes and edges
+    /// are kept as they were loaded, the positions were handed out in file order.
+    fn desc(&self) -> GraphDesc {
+        let mut desc = GraphDesc {
+            alpha: self.state.alpha,
+            betweenness: self.betweenness,
+            capacity: self.capacity,
+            attack: self.attack.clone(),
+            pipeline: self.pipeline_name().map(str::to_owned),
+            ..self.saved.clone()
+        };
+
+        for (i, node) in desc.nodes.iter_mut().enumerate() {
+            node.position = self.position(i);
+        }
+
+        desc
+    }
+
+    /// [`Self::desc`] as written to the graph file. Overrides of the command line the
+    /// user left alone keep the values of the file.
+    fn file_desc(&self) -> GraphDesc {
+        let mut desc = self.desc();
+        if desc.alpha == self.unchanged.alpha {
+            desc.alpha = self.saved.alpha;
+        }
+        if desc.pipeline == self.unchanged.pipeline {
+            desc.pipeline = self.saved.pipeline.clone();
+        }
+
+        desc
+    }
+
+    /// Name of the selected pipeline as written in the graph file.
+    fn pipeline_name(&self) -> Option<&str> {
+        (self.pipeline != 0).then(|| self.pipelines[self.pipeline].name())
+    }
+
+    fn position(&self, i: usize) -> [f32; 3] {
+        self.world.positions[unsafe { Node::new(i as u32) }]
+            .0
+            .into()
+    }
+
+    pub fn graph_path(&self) -> &Path {
+        &self.graph_path
+    }
+
+    /// Whether the parameters or node positions differ from the graph file.
+    pub fn is_dirty(&self) -> bool {
+        let unchanged = &self.unchanged;
+
+        unchanged.alpha != self.state.alpha
+            || unchanged.betweenness != self.betweenness
+            || unchanged.capacity != self.capacity
+            || unchanged.attack != self.attack
+            || unchanged.pipeline.as_deref() != self.pipeline_name()
+            || unchanged
+                .nodes
+                .iter()
+                .enumerate()
+                .any(|(i, node)| node.position != self.position(i))
+    }
+
+    pub fn save(&mut self) {
+        self.save_as(self.graph_path.clone());
+    }
+
+    /// Saves the graph to `path`, which the following saves and reverts then use.
+    pub fn save_as(&mut self, path: PathBuf) {
+        let desc = self.file_desc();
+        match desc.save(&path) {
+            Ok(()) => {
+                info!("Saved the graph to {}", path.display());
+                self.saved = desc;
+                self.unchanged = self.desc();
+                self.graph_path = path;
+                self.file_error = None;
+                self.graph_errors.clear();
+            }
+            Err(err) => {
+                error!("{err}");
+                self.file_error = Some(err.to_string());
+            }
+        }
+    }
+
+    /// Reloads the graph file, dropping the unsaved changes and the runs made with them.
+    pub fn revert(&mut self) {
+        let desc = match GraphDesc::load(&self.graph_path) {
+            Ok(desc) => desc,
+            Err(LoadError::Invalid { path, errors }) => {
+                for err in errors.iter() {
+                    error!("{}: {err}", path.display());
+                }
+                self.file_error = Some(format!("Not reverted, {} is invalid", path.display()));
+                self.graph_errors = errors;
+                return;
+            }
+            Err(err) => {
+                error!("{err}");
+                self.file_error = Some(err.to_string());
+                return;
+            }
+        };
+
+        let state = match State::initial(desc.clone()) {
+            Ok(state) => state,
+            Err(errors) => {
+                for err in errors.iter() {
+                    error!("{}: {err}", self.graph_path.display());
+                }
+                self.file_error = Some(format!(
+                    "Not reverted, {} is invalid",
+                    self.graph_path.display()
+                ));
+                self.graph_errors = errors;
+                return;
+            }
+        };
+
+        info!("Reverted to {}", self.graph_path.display());
+        self.cancel();
+        self.state = state;
+        self.cascade_tree = None;
+        self.betweenness = desc.betweenness;
+        self.capacity = desc.capacity;
+        self.attack = desc.attack.clone();
+        self.pipeline = Self::find_pipeline(&self.pipelines, &desc, &mut self.pipeline_errors);
+
+        // The camera stays where it is.
+        let world = WorldData::new(&self.state.at(0).graph.tracker, desc.clone());
+        self.world.positions = world.positions;
+        self.world.materials = world.materials;
+
+        self.selected_node = None;
+        self.wave = None;
+        self.sweep = None;
+        self.file_error = None;
+        self.graph_errors.clear();
+        self.saved = desc;
+        self.unchanged = self.desc();
+        self.recompute();
+    }
+
+    /// Starts an ensemble of the current pipeline in place of the running batch.
+    pub fn run_ensemble(&mut self, runs: usize, seed: u64) {
+        let ensemble = Ensemble::new(runs, seed);
+        let pipeline = self.pipelines[self.pipeline].clone();
+        self.batch_progress = (0, ensemble.runs());
+        self.batch = Some(ComputeWorker::spawn_ensemble(
+            ensemble,
+            self.desc(),
+            pipeline,
+        ));
+    }
+
+    /// Starts a sweep of the current graph in place of the running batch.
+    pub fn run_sweep(&mut self, alphas: RangeInclusive<f64>, steps: usize, threshold: f64) {
+        let sweep = Sweep::new(alphas, steps, threshold);
+        self.batch_progress = (0, sweep.max_runs());
+        self.batch = Some(ComputeWorker::spawn_sweep(sweep, self.desc()));
+    }
+
+    pub fn cancel_batch(&mut self) {
+        if let Some(batch) = self.batch.as_ref() {
+            batch.cancel();
+        }
+    }
+
+    /// Cascade tree of the iterations finished so far.
+    pub fn cascade_tree(&mut self) -> &CascadeTree {
+        self.cascade_tree
+            .get_or_insert_with(|| self.state.cascade_tree())
+    }
+
+    pub fn export_cascade_tree(&mut self) {
+        let contents = self.cascade_tree().to_toml();
+        Self::export(
+            "the cascade tree",
+            &self.output.join(CASCADE_TREE_FILE),
+            contents,
+        );
+    }
+
+    pub fn export_failure_history(&self) {
+        Self::export(
+            "the failure history",
+            &self.output.join(FAILURE_HISTORY_FILE),
+            self.state.failure_history().to_toml(),
+        );
+    }
+
+    fn export(what: &str, path: &Path, contents: Result<String, toml::ser::Error>) {
+        let contents = match contents {
+            Ok(contents) => contents,
+            Err(err) => {
+                error!("Failed to serialize {what}: {err}");
+                return;
+            }
+        };
+
+        match std::fs::write(path, contents) {
+            Ok(()) => info!("Exported {what} to {}", path.display()),
+            Err(err) => error!("Failed to write {}: {err}", path.display()),
+        }
+    }
+}
+
+pub struct MyApp {
+    renderer: Option<Renderer>,
+    /// Directory of the shaders and meshes.
+    data: PathBuf,
+    app_state: AppState,
+    ui_state: UiState,
+    input: Input,
+}
+
+impl MyApp {
+    pub fn init(args: &Args) -> Result<Self, LoadError> {
+        Ok(Self {
+            renderer: None,
+            data: args.data.clone(),
+            app_state: AppState::new(args)?,
+            ui_state: Default::default(),
+            input: Default::default(),
+        })
+    }
+}
+
+impl UserApp for MyApp {
+    fn init_renderer(&mut self, gl: Arc<glow::Context>) {
+        self.renderer = Some(Renderer::new(gl.clone(), &self.data));
+    }
+
+    fn update(&mut self, delta: Duration) {
+        self.app_state.poll();
+
+        if self.input.is_pressed(Key::W) {
+            self.app_state
+                .world
+                .camera
+                .process_keyboard(CameraMovement::Up, delta.as_secs_f32());
+        }
+
+        if self.input.is_pressed(Key::S) {
+            self.app_state
+                .world
+                .camera
+                .process_keyboard(CameraMovement::Down, delta.as_secs_f32());
+        }
+
+        if self.input.is_pressed(Key::A) {
+            self.app_state
+                .world
+                .camera
+                .process_keyboard(CameraMovement::Left, delta.as_secs_f32());
+        }
+
+        if self.input.is_pressed(Key::D) {
+            self.app_state
+                .world
+                .camera
+                .process_keyboard(CameraMovement::Right, delta.as_secs_f32());
+        }
+
+        if self.input.is_pressed(Key::ArrowUp) {
+            self.app_state
+                .world
+                .camera
+                .process_keyboard(CameraMovement::Forward, delta.as_secs_f32());
+        }
+
+        if self.input.is_pressed(Key::ArrowDown) {
+            self.app_state
+                .world
+                .camera
+                .process_keyboard(CameraMovement::Backward, delta.as_secs_f32());
+        }
+
+        if self.input.is_pressed(Key::Lmb) && self.input.is_pressed(Key::Rmb) {
+            self.app_state
+                .world
+                .camera
+                .process_mouse_zoom(self.input.mouse_motion.1, delta.as_secs_f32());
+        } else if self.input.is_pressed(Key::Lmb) {
+            self.app_state
+                .world
+                .camera
+                .process_mouse_motion(self.input.mouse_motion, delta.as_secs_f32());
+        } else if self.input.is_pressed(Key::Rmb) {
+            if let Some(ref renderer) = self.renderer {
+                let idx = renderer.idx_from_stencil(self.input.mouse_position);
+                if idx != 0 {
+                    self.app_state.selected_node = Some(unsafe { Node::new(idx as u32 - 1) });
+                } else {
+                    self.app_state.selected_node = None;
+                }
+            }
+
+            if let Some(node) = self.app_state.selected_node {
+                let position = &mut self.app_state.world.positions[node].0;
+                let (x, y) = self.input.mouse_motion;
+                position.x += x as f32 * 3.5 * delta.as_secs_f32();
+                position.y -= y as f32 * 3.5 * delta.as_secs_f32();
+            }
+        }
+
+        if self.input.is_pressed(Key::Lctrl) && self.input.is_pressed(Key::Lmb) {
+            let pos = self.input.mouse_to_world(&self.app_state.world);
+            info!("{pos:?}");
+        }
+
+        self.input.update();
+    }
+
+    fn render(&mut self, _gl: Arc<glow::Context>) {
+        let Some(ref renderer) = self.renderer else {
+            return;
+        };
+
+        let state = &self.app_state.state;
+        self.app_state.world.update_materials(
+            state.get(),
+            &state.failure_history(),
+            state.current_iter(),
+        );
+        renderer.render(&self.app_state);
+    }
+
+    fn ui_layout(&mut self, egui_ctx: &egui::Context) {
+        self.ui_state.show(egui_ctx, &mut self.app_state);
+    }
+
+    fn handle_window_events(&mut self, event: WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput {
+                device_id: _,
+                event,
+                is_synthetic: _,
+            } => {
+                if let PhysicalKey::Code(code) = event.physical_key {
+                    if matches!(code, KeyCode::KeyW) {
+                        self.input.set(Key::W, event.state);
+                    }
+
+                    if matches!(code, KeyCode::KeyS) {
+                        self.input.set(Key::S, event.state);
+                    }
+
+                    if matches!(code, KeyCode::KeyA) {
+                        self.input.set(Key::A, event.state);
+                    }
+
+                    if matches!(code, KeyCode::KeyD) {
+                        self.input.set(Key::D, event.state);
+                    }
+
+                    if matches!(code, KeyCode::ArrowUp) {
+                        self.input.set(Key::ArrowUp, event.state);
+                    }
+
+                    if matches!(code, KeyCode::ArrowDown) {
+                        self.input.set(Key::ArrowDown, event.state);
+                    }
+
+                    if matches!(code, KeyCode::ControlLeft) {
+                        self.input.set(Key::Lctrl, event.state);
+                    }
+
+                    if matches!(code, KeyCode::AltLeft) {
+                        self.input.set(Key::Lalt, event.state);
+                    }
+                }
+            }
+            WindowEvent::MouseInput {
+                device_id: _,
+                state,
+                button,
+            } => {
+                if matches!(button, MouseButton::Left) {
+                    self.input.set(Key::Lmb, state);
+                }
+
+                if matches!(button, MouseButton::Right) {
+                    self.input.set(Key::Rmb, state);
+                }
+            }
+            WindowEvent::CursorMoved {
+                device_id: _,
+                position,
+            } => {
+                self.input.mouse_position = position.into();
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_device_events(&mut self, event: winit::event::DeviceEvent) {
+        match event {
+            DeviceEvent::MouseWheel { delta } => self.input.mouse_wheel = delta,
+            DeviceEvent::MouseMotion { delta } => self.input.mouse_motion = delta,
+            _ => {}
+        }
+    }
+}
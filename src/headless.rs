@@ -0,0 +1,262 @@
+use std::{
+    fmt,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use serde::Serialize;
+
+use crate::{
+    cli::Args,
+    compute::{
+        ComputeError, pipeline,
+        state::{State, WaveKind},
+    },
+    graph::LoadError,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Format {
+    /// An aligned table for reading in a terminal.
+    #[default]
+    Text,
+    Csv,
+    Json,
+}
+
+impl Format {
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum HeadlessError {
+    Load(LoadError),
+    Io { path: PathBuf, error: io::Error },
+    UnknownPipeline(String),
+    Compute(ComputeError),
+}
+
+impl fmt::Display for HeadlessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Load(error) => write!(f, "{error}"),
+            Self::Io { path, error } => write!(f, "{}: {error}", path.display()),
+            Self::UnknownPipeline(name) => write!(f, "unknown pipeline `{name}`"),
+            Self::Compute(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for HeadlessError {}
+
+/// Metrics and deletions of one iteration, nodes by their id in the graph file.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct IterationRecord {
+    pub iteration: usize,
+    pub alive: usize,
+    pub alive_fraction: f64,
+    pub giant_component: f64,
+    pub relative_efficiency: f64,
+    pub beta_delta: f64,
+    pub attacked: Vec<u32>,
+    pub overloaded: Vec<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RunRecord {
+    pub pipeline: String,
+    pub alpha: f64,
+    pub stop_reason: String,
+    pub iterations: Vec<IterationRecord>,
+}
+
+impl RunRecord {
+    fn iterations(state: &State) -> Vec<IterationRecord> {
+        let metric = |metrics: &[f64], i: usize| metrics.get(i).copied().unwrap_or(f64::NAN);
+
+        (0..state.iter_count())
+            .map(|i| {
+                let iteration = state.at(i);
+                let graph = &iteration.graph;
+                let deleted = |kind| {
+                    iteration
+                        .waves
+                        .iter()
+                        .filter(|wave| wave.kind == kind)
+                        .flat_map(|wave| wave.failures.iter())
+                        .map(|failure| failure.node.as_idx() as u32)
+                        .collect()
+                };
+
+                IterationRecord {
+                    iteration: i,
+                    alive: graph.alive(),
+                    alive_fraction: graph.alive() as f64 / graph.node_count() as f64,
+                    giant_component: metric(&state.giant_component, i),
+                    relative_efficiency: metric(&state.relative_efficiency, i),
+                    beta_delta: metric(&state.beta_deltas, i),
+                    attacked: deleted(WaveKind::Attack),
+                    overloaded: deleted(WaveKind::Overload),
+                }
+            })
+            .collect()
+    }
+
+    fn write(&self, format: Format, out: &mut dyn Write) -> io::Result<()> {
+        let ids = |ids: &[u32], separator| {
+            ids.iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(separator)
+        };
+
+        match format {
+            Format::Json => {
+                serde_json::to_writer_pretty(&mut *out, self)?;
+                writeln!(out)
+            }
+            Format::Csv => {
+                writeln!(
+                    out,
+                    "iteration,alive,alive_fraction,giant_component,relative_efficiency,beta_delta,attacked,overloaded"
+                )
+                .and_then(|_| {
+                    self.iterations.iter().try_for_each(|i| {
+                        writeln!(
+                            out,
+                            "{},{},{},{},{},{},{},{}",
+                            i.iteration,
+                            i.alive,
+                            i.alive_fraction,
+                            i.giant_component,
+                            i.relative_efficiency,
+                            i.beta_delta,
+                            ids(&i.attacked, ";"),
+                            ids(&i.overloaded, ";"),
+                        )
+                    })
+                })
+            }
+            Format::Text => {
+                writeln!(
+                    out,
+                    "pipeline `{}`, alpha {}, stopped: {}",
+                    self.pipeline, self.alpha, self.stop_reason
+                )
+                .and_then(|_| {
+                    writeln!(
+                        out,
+                        "{:>9} {:>7} {:>7} {:>7} {:>10}  deleted",
+                        "iteration", "alive", "G", "E/E0", "beta delta"
+                    )
+                })
+                .and_then(|_| {
+                    self.iterations.iter().try_for_each(|i| {
+                        writeln!(
+                            out,
+                            "{:>9} {:>7} {:>7.3} {:>7.3} {:>10.3}  {}",
+                            i.iteration,
+                            i.alive,
+                            i.giant_component,
+                            i.relative_efficiency,
+                            i.beta_delta,
+                            ids(&[i.attacked.as_slice(), &i.overloaded].concat(), " "),
+                        )
+                    })
+                })
+            }
+        }
+    }
+}
+
+/// Runs the pipeline of the graph file to the end without opening a window.
+pub fn run(args: &Args) -> Result<RunRecord, HeadlessError> {
+    let desc = args.load_graph().map_err(HeadlessError::Load)?;
+
+    let (pipelines, errors) = pipeline::load(&args.pipelines());
+    for err in errors.iter() {
+        log::error!("{err}");
+    }
+
+    let pipeline = match desc.pipeline.as_deref() {
+        Some(name) => pipelines
+            .iter()
+            .find(|p| p.name() == name)
+            .ok_or_else(|| HeadlessError::UnknownPipeline(name.to_owned()))?,
+        None => &pipelines[0],
+    };
+
+    let mut compute = pipeline.compute(&desc).map_err(HeadlessError::Compute)?;
+    let reason = compute.run().map_err(HeadlessError::Compute)?;
+
+    let record = RunRecord {
+        pipeline: pipeline.name().to_owned(),
+        alpha: desc.alpha,
+        stop_reason: reason.to_string(),
+        iterations: RunRecord::iterations(compute.state()),
+    };
+
+    let (path, written) = match &args.output {
+        Some(dir) => {
+            let path = dir.join(format!("iterations.{}", args.format.extension()));
+            let written = std::fs::File::create(&path).and_then(|file| {
+                let mut file = io::BufWriter::new(file);
+                record.write(args.format, &mut file)?;
+                file.flush()
+            });
+            (path, written)
+        }
+        None => (
+            "<stdout>".into(),
+            record.write(args.format, &mut io::stdout().lock()),
+        ),
+    };
+    written.map_err(|error| HeadlessError::Io { path, error })?;
+
+    Ok(record)
+}
+
+mod test {
+    #![allow(unused_imports)]
+    use super::{Format, RunRecord};
+    use crate::compute::pipeline::{PipelineDesc, Registry};
+    use crate::graph::GraphDesc;
+
+    #[test]
+    fn record_test() {
+        let pipeline = Registry::default()
+            .validate(&PipelineDesc::default())
+            .unwrap();
+        let mut compute = pipeline.compute(&GraphDesc::example()).unwrap();
+        let reason = compute.run().unwrap();
+
+        let record = RunRecord {
+            pipeline: pipeline.name().to_owned(),
+            alpha: 0.0,
+            stop_reason: reason.to_string(),
+            iterations: RunRecord::iterations(compute.state()),
+        };
+        assert_eq!(record.iterations.len(), compute.state().iter_count());
+        assert!(record.iterations[0].attacked.is_empty());
+        assert!(record.iterations[1..].iter().all(|i| i.attacked.len() == 1));
+
+        let mut csv = vec![];
+        record.write(Format::Csv, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), record.iterations.len() + 1);
+
+        let mut json = vec![];
+        record.write(Format::Json, &mut json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(
+            json["iterations"].as_array().unwrap().len(),
+            record.iterations.len()
+        );
+    }
+}
@@ -91,6 +91,7 @@ impl Input {
         self.get(key) == ElementState::Pressed
     }
 
+    #[allow(unused)]
     pub fn is_released(&self, key: Key) -> bool {
         self.get(key) == ElementState::Released
     }
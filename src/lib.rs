@@ -0,0 +1,6 @@
+//! The cascade engine: graphs and the pipelines computing their cascading failures.
+//! The window showing them is the `final-project` binary, built with the `gui` feature.
+
+pub mod compute;
+pub mod config;
+pub mod graph;
@@ -1,16 +1,19 @@
 use std::marker::PhantomData;
 
 pub(super) trait ScopedBind: Sized {
+    #[allow(unused)]
     fn scoped_bind<'a>(&self, gl: &'a glow::Context) -> UnbindOnDrop<'a, Self>;
     fn unbind(gl: &glow::Context);
 }
 
+#[allow(unused)]
 pub(super) struct UnbindOnDrop<'a, T: ScopedBind> {
     gl: &'a glow::Context,
     _a: PhantomData<T>,
 }
 
 impl<'a, T: ScopedBind> UnbindOnDrop<'a, T> {
+    #[allow(unused)]
     pub(super) fn new(gl: &'a glow::Context) -> Self {
         Self {
             gl,
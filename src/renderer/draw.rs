@@ -1,11 +1,12 @@
-use cgmath::Matrix4;
+use cgmath::{InnerSpace, Matrix4, Vector3};
 use glow::HasContext;
-use log::info;
 
 use crate::{
-    AppState,
-    compute::state::Iteration,
-    graph::{Graph, node::NodeStatusTracker},
+    graph::{
+        Graph,
+        adjacency::Edge,
+        node::{Node, NodeStatusTracker},
+    },
     world::{Material, Position, WorldData, mat4_to_vec},
 };
 
@@ -18,9 +19,8 @@ pub(super) struct NodeDrawItem {
 }
 
 impl NodeDrawItem {
-    pub(super) fn build(world: &WorldData, tracker: &NodeStatusTracker) -> Vec<Self> {
-        tracker
-            .iter_alive()
+    pub(super) fn build(world: &WorldData, nodes: impl Iterator<Item = Node>) -> Vec<Self> {
+        nodes
             .map(|i| Self {
                 position: world.positions[i],
                 material: world.materials[i],
@@ -85,21 +85,63 @@ pub(super) struct EdgeDrawItem {
 }
 
 impl EdgeDrawItem {
-    pub(super) fn build(world: &WorldData, graph: &Graph) -> Vec<Self> {
+    /// Radius of the node sphere mesh, arrowheads end on its surface.
+    const NODE_RADIUS: f32 = 1.0;
+    const ARROW_LENGTH: f32 = 0.8;
+
+    /// Edges of `graph` between the nodes alive in `tracker`, which may have fewer alive
+    /// nodes than the graph while a cascade is shown wave by wave.
+    pub(super) fn build(
+        world: &WorldData,
+        graph: &Graph,
+        tracker: &NodeStatusTracker,
+    ) -> Vec<Self> {
         let mut ret = vec![];
 
-        for i in graph.tracker.iter_alive() {
-            for j in graph.tracker.iter_alive().exclude(i) {
-                if graph.is_adjacent(i, j) {
-                    ret.push(EdgeDrawItem {
-                        positions: [world.positions[i], world.positions[j]],
-                    });
+        for i in tracker.iter_alive() {
+            for Edge { node: j, .. } in graph.neighbours(i) {
+                if !tracker.is_alive(j) {
+                    continue;
+                }
+
+                // Undirected edges are listed by both endpoints, draw them once.
+                if !graph.is_directed() && j < i {
+                    continue;
+                }
+
+                let positions = [world.positions[i], world.positions[j]];
+                ret.push(EdgeDrawItem { positions });
+
+                if graph.is_directed() {
+                    ret.extend(Self::arrowhead(positions));
                 }
             }
         }
         ret
     }
 
+    /// Two short segments forming a head at the `to` end of the edge. The head lies in
+    /// the XY plane the graph is laid out in.
+    fn arrowhead([from, to]: [Position; 2]) -> impl Iterator<Item = Self> {
+        let edge = to.0 - from.0;
+        let dir = if edge.magnitude2() > 0.0 {
+            edge.normalize()
+        } else {
+            Vector3::unit_x()
+        };
+        let side = Vector3::new(-dir.y, dir.x, 0.0);
+
+        let tip = to.0 - dir * Self::NODE_RADIUS;
+        let base = tip - dir * Self::ARROW_LENGTH;
+
+        [1.0, -1.0].into_iter().map(move |sign| EdgeDrawItem {
+            positions: [
+                Position(base + side * sign * Self::ARROW_LENGTH * 0.5),
+                Position(tip),
+            ],
+        })
+    }
+
     pub(super) fn set_per_pass_uniforms(gl: &glow::Context, shader: Shader, world: &WorldData) {
         let view_proj_loc = shader.uniform_location(gl, "ViewProj").unwrap();
         let view_proj = mat4_to_vec(world.projection * world.camera.view_mat());
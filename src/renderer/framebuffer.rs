@@ -1,5 +1,4 @@
 use glow::HasContext;
-use log::info;
 
 use crate::unbind_on_drop;
 
@@ -9,6 +8,7 @@ use super::binding::{ScopedBind, UnbindOnDrop};
 pub(super) struct Framebuffer {
     pub fbo: glow::Framebuffer,
     pub color_buffer: glow::Texture,
+    #[allow(unused)]
     pub depth_buffer: Option<glow::Renderbuffer>,
 }
 
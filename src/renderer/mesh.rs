@@ -1,3 +1,5 @@
+use std::path::{Path, PathBuf};
+
 use bytemuck::{NoUninit, cast_slice};
 use glow::HasContext;
 use log::info;
@@ -12,7 +14,7 @@ trait Vertex: Clone + Copy + NoUninit {
 
 #[repr(C)]
 #[derive(Clone, Copy, NoUninit)]
-struct NodeVertex {
+pub(super) struct NodeVertex {
     position: [f32; 3],
     normal: [f32; 3],
 }
@@ -37,9 +39,13 @@ impl Vertex for NodeVertex {
 }
 
 impl NodeVertex {
-    fn load_sphere() -> (Vec<Self>, Option<Vec<u32>>) {
+    pub(super) fn sphere_path(data: &Path) -> PathBuf {
+        data.join("meshes").join("sphere.obj")
+    }
+
+    fn load_sphere(data: &Path) -> (Vec<Self>, Option<Vec<u32>>) {
         let (models, _) =
-            tobj::load_obj("data/meshes/sphere.obj", &tobj::LoadOptions::default()).unwrap();
+            tobj::load_obj(Self::sphere_path(data), &tobj::LoadOptions::default()).unwrap();
 
         let tobj::Model { mesh, name } = models.into_iter().next().unwrap();
         info!("Loading model {name}");
@@ -116,8 +122,11 @@ impl FullscreenTriangleVertex {
 #[derive(Copy, Clone)]
 pub(super) struct Mesh {
     pub vao: glow::VertexArray,
+    #[allow(unused)]
     pub vbo: glow::Buffer,
+    #[allow(unused)]
     pub ibo: Option<glow::Buffer>,
+    #[allow(unused)]
     pub vertex_count: usize,
     pub index_count: Option<usize>,
 }
@@ -194,9 +203,9 @@ pub(super) struct Meshes {
 }
 
 impl Meshes {
-    pub(super) fn new(gl: &glow::Context) -> Self {
+    pub(super) fn new(gl: &glow::Context, data: &Path) -> Self {
         Self {
-            node: Mesh::new(gl, NodeVertex::load_sphere()),
+            node: Mesh::new(gl, NodeVertex::load_sphere(data)),
             fullscreen: Mesh::new(gl, (FullscreenTriangleVertex::new(), None)),
         }
     }
@@ -1,17 +1,19 @@
-use std::sync::Arc;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use binding::ScopedBind;
 use draw::{EdgeDrawItem, NodeDrawItem};
 use framebuffer::{Framebuffer, FramebufferBuilder};
 use glow::HasContext;
-use log::info;
-use mesh::{Mesh, Meshes};
+use mesh::{Mesh, Meshes, NodeVertex};
 use shader::{Shader, Shaders};
 
 use crate::{
     AppState,
     app::{WINDOW_HEIGHT, WINDOW_WIDTH},
-    world::{WorldData, mat4_to_vec},
+    world::WorldData,
 };
 
 pub mod draw;
@@ -29,15 +31,24 @@ pub struct Renderer {
 }
 
 impl Renderer {
-    pub fn new(gl: Arc<glow::Context>) -> Self {
+    /// Files of the data directory the renderer loads, all of them must exist.
+    pub fn assets(data: &Path) -> Vec<PathBuf> {
+        Shaders::NAMES
+            .iter()
+            .flat_map(|name| Shader::paths(data, name))
+            .chain([NodeVertex::sphere_path(data)])
+            .collect()
+    }
+
+    pub fn new(gl: Arc<glow::Context>, data: &Path) -> Self {
         unsafe {
             gl.enable(glow::MULTISAMPLE);
             gl.enable(glow::DEPTH_TEST);
             gl.depth_func(glow::LESS);
         }
 
-        let meshes = Meshes::new(gl.as_ref());
-        let shaders = Shaders::new(gl.as_ref());
+        let meshes = Meshes::new(gl.as_ref(), data);
+        let shaders = Shaders::new(gl.as_ref(), data);
         let framebuffer =
             FramebufferBuilder::new(crate::app::WINDOW_WIDTH, crate::app::WINDOW_HEIGHT)
                 .with_depth(true)
@@ -52,10 +63,18 @@ impl Renderer {
     }
 
     pub fn render(&self, app_state: &AppState) {
-        let tracker = &app_state.compute.state().get().graph.tracker;
-        let node_items = NodeDrawItem::build(&app_state.world, tracker);
-        let edge_items =
-            EdgeDrawItem::build(&app_state.world, &app_state.compute.state().get().graph);
+        let state = &app_state.state;
+        let (graph, tracker) = match app_state.wave {
+            Some(wave) => state.after_waves(state.current_iter(), wave + 1),
+            None => (&state.get().graph, state.get().graph.tracker.clone()),
+        };
+        let history = state.failure_history();
+        let failed = history
+            .failed_by(state.current_iter())
+            .map(|(node, _)| node)
+            .filter(|&node| app_state.show_failed && !tracker.is_alive(node));
+        let node_items = NodeDrawItem::build(&app_state.world, tracker.iter_alive().chain(failed));
+        let edge_items = EdgeDrawItem::build(&app_state.world, graph, &tracker);
 
         let gl = self.gl.as_ref();
 
@@ -1,3 +1,5 @@
+use std::path::{Path, PathBuf};
+
 use glow::HasContext;
 use log::info;
 
@@ -9,14 +11,20 @@ use super::{ScopedBind, binding::UnbindOnDrop};
 pub(super) struct Shader(pub glow::Program);
 
 impl Shader {
-    fn new(gl: &glow::Context, name: &str) -> Self {
-        let shaders_folder = "data/shaders/";
-        let shader_path = String::from(shaders_folder) + name;
-        let vs_path = shader_path.clone() + "_vs.glsl";
-        let fs_path = shader_path + "_fs.glsl";
+    /// Files of the shader `name` in the data directory.
+    pub(super) fn paths(data: &Path, name: &str) -> [PathBuf; 2] {
+        let shaders_folder = data.join("shaders");
+        [
+            shaders_folder.join(format!("{name}_vs.glsl")),
+            shaders_folder.join(format!("{name}_fs.glsl")),
+        ]
+    }
 
-        info!("Loading vertex shader in path {vs_path}");
-        info!("Loading fragment shader in path {fs_path}");
+    fn new(gl: &glow::Context, data: &Path, name: &str) -> Self {
+        let [vs_path, fs_path] = Self::paths(data, name);
+
+        info!("Loading vertex shader in path {}", vs_path.display());
+        info!("Loading fragment shader in path {}", fs_path.display());
 
         let vs_src = std::fs::read_to_string(vs_path).unwrap();
         let fs_src = std::fs::read_to_string(fs_path).unwrap();
@@ -98,11 +106,14 @@ pub(super) struct Shaders {
 }
 
 impl Shaders {
-    pub(super) fn new(gl: &glow::Context) -> Self {
+    pub(super) const NAMES: [&str; 3] = ["node", "edge", "fullscreen"];
+
+    pub(super) fn new(gl: &glow::Context, data: &Path) -> Self {
+        let [node, edge, fullscreen] = Self::NAMES.map(|name| Shader::new(gl, data, name));
         Self {
-            node: Shader::new(gl, "node"),
-            edge: Shader::new(gl, "edge"),
-            fullscreen: Shader::new(gl, "fullscreen"),
+            node,
+            edge,
+            fullscreen,
         }
     }
 }
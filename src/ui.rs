@@ -1,70 +1,250 @@
 use cgmath::{Quaternion, Vector3};
-use egui::{Label, Slider};
+use egui::{Button, Color32, DragValue, Label, ProgressBar, Slider};
 use egui_extras::{Column, TableBuilder};
+use egui_plot::{Line, PlotPoints, PlotUi, Polygon};
 use transform_gizmo_egui::{
     Gizmo, GizmoConfig, GizmoExt, GizmoMode, GizmoOrientation, math::Transform, mint,
 };
 
 use crate::{
     AppState,
-    compute::state::Iteration,
+    compute::{
+        ensemble::Stats,
+        history::FailedAt,
+        state::{Iteration, Wave},
+        tree::{CascadeEvent, CascadeTree},
+    },
+    graph::node::Node,
     world::{Material, Position},
 };
 
-#[derive(Default)]
+const BAND_COLOR: Color32 = Color32::from_rgb(100, 150, 250);
+
 pub struct UiState {
+    /// Iteration picked by the user, viewed once a rerun computed it again.
     selected_item: usize,
     material: Material,
+    ensemble_runs: usize,
+    ensemble_seed: u64,
+    sweep_from: f64,
+    sweep_to: f64,
+    sweep_steps: usize,
+    sweep_threshold: f64,
+    /// Path typed for Save As, the graph file until edited.
+    save_as_path: String,
+}
+
+impl Default for UiState {
+    fn default() -> Self {
+        Self {
+            selected_item: 0,
+            material: Default::default(),
+            ensemble_runs: 100,
+            ensemble_seed: 0,
+            sweep_from: 0.0,
+            sweep_to: 2.0,
+            sweep_steps: 11,
+            sweep_threshold: 0.5,
+            save_as_path: String::new(),
+        }
+    }
 }
 
 impl UiState {
     pub fn show(&mut self, egui_ctx: &egui::Context, app_state: &mut AppState) {
+        self.show_graph_file(egui_ctx, app_state);
         self.show_compute_info(egui_ctx, app_state);
         self.show_material_editor(egui_ctx, app_state);
         self.show_plots(egui_ctx, app_state);
+        self.show_sweep(egui_ctx, app_state);
+        self.show_cascade_tree(egui_ctx, app_state);
+    }
+
+    fn show_graph_file(&mut self, egui_ctx: &egui::Context, app_state: &mut AppState) {
+        if self.save_as_path.is_empty() {
+            self.save_as_path = app_state.graph_path().display().to_string();
+        }
+
+        let dirty = app_state.is_dirty();
+
+        egui::Window::new("Graph File")
+            .resizable([true, false])
+            .show(egui_ctx, |ui| {
+                ui.add(Label::new(format!(
+                    "{}{}",
+                    app_state.graph_path().display(),
+                    if dirty { " (unsaved changes)" } else { "" }
+                )));
+
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(dirty, Button::new("Save")).clicked() {
+                        app_state.save();
+                    }
+                    if ui.add_enabled(dirty, Button::new("Revert")).clicked() {
+                        app_state.revert();
+                    }
+                    ui.checkbox(&mut app_state.autosave, "Save on exit");
+                });
+
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.save_as_path);
+                    if ui.button("Save As").clicked() {
+                        app_state.save_as(self.save_as_path.clone().into());
+                    }
+                });
+
+                if let Some(err) = app_state.file_error.as_ref() {
+                    ui.colored_label(Color32::RED, err);
+                }
+
+                // Clicking a problem selects its node if the current graph has it.
+                let node_count = app_state.state.at(0).graph.node_count();
+                let mut selected = None;
+                for err in app_state.graph_errors.iter() {
+                    match err.node().filter(|&node| (node as usize) < node_count) {
+                        Some(node) => {
+                            if ui.link(err.to_string()).clicked() {
+                                selected = Some(unsafe { Node::new(node) });
+                            }
+                        }
+                        None => {
+                            ui.colored_label(Color32::RED, err.to_string());
+                        }
+                    }
+                }
+                if selected.is_some() {
+                    app_state.selected_node = selected;
+                }
+            });
     }
 
     fn show_compute_info(&mut self, egui_ctx: &egui::Context, app_state: &mut AppState) {
-        app_state
-            .compute
-            .state_mut()
-            .set_current_iter(self.selected_item);
+        // A rerun streams its iterations from the first one, the picked iteration is
+        // kept and viewed again once it arrives.
+        let viewed_item = self.selected_item.min(app_state.state.iter_count() - 1);
+        app_state.state.set_current_iter(viewed_item);
+        let mut item = viewed_item;
 
-        let compute_state = app_state.compute.state();
+        let compute_state = &app_state.state;
         let selected_node = app_state.selected_node;
-        let mut alpha = app_state.compute.state().alpha;
+        let computing = app_state.worker.is_some();
+        let mut alpha = app_state.state.alpha;
+        let mut pipeline = app_state.pipeline;
+        let mut show_failed = app_state.show_failed;
+        let history = compute_state.failure_history();
+        let mut wave = app_state
+            .wave
+            .filter(|&wave| wave < compute_state.get().waves.len());
+        let mut recompute = false;
+        let mut cancel = false;
 
         egui::Window::new("Compute Info")
             .resizable(true)
             .default_width(1000.0)
             .show(egui_ctx, |ui| {
-                ui.add(Slider::new(&mut alpha, 0.0..=5.0).text("Alpha"));
+                if computing {
+                    let last = &compute_state.at(compute_state.iter_count() - 1).graph;
+                    let (alive, count) = (last.alive(), last.node_count());
+
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            ProgressBar::new(1.0 - alive as f32 / count as f32)
+                                .text(format!(
+                                    "{} iterations, {alive}/{count} nodes alive",
+                                    compute_state.iter_count()
+                                ))
+                                .animate(true),
+                        );
+                        cancel = ui.button("Cancel").clicked();
+                    });
+                }
+
+                egui::ComboBox::from_label("Pipeline")
+                    .selected_text(app_state.pipelines[pipeline].name())
+                    .show_ui(ui, |ui| {
+                        for (i, p) in app_state.pipelines.iter().enumerate() {
+                            ui.selectable_value(&mut pipeline, i, p.name());
+                        }
+                    });
+
+                for err in app_state.pipeline_errors.iter() {
+                    ui.colored_label(Color32::RED, err);
+                }
+
+                // Dragging only moves the value, the cascade is rerun once it is released.
+                let response = ui.add(Slider::new(&mut alpha, 0.0..=5.0).text("Alpha"));
+                recompute = response.drag_stopped() || (response.changed() && !response.dragged());
 
                 ui.add(Label::new(format!(
                     "Beta = {:.2}",
                     compute_state.get().info.beta
                 )));
 
+                let Iteration { graph, info, .. } = compute_state.get();
+                ui.add(Label::new(format!(
+                    "Components = {}, G = {:.2}",
+                    info.components.count(),
+                    info.components.largest_size() as f64 / graph.node_count() as f64
+                )));
+
+                ui.add(Label::new(format!(
+                    "E = {:.3}, E / E0 = {:.2}",
+                    info.efficiency, info.relative_efficiency
+                )));
+
+                match &app_state.outcome {
+                    Some(Ok(reason)) => {
+                        ui.add(Label::new(format!("Stopped: {reason}")));
+                    }
+                    Some(Err(err)) => {
+                        ui.colored_label(Color32::RED, format!("Failed: {err}"));
+                    }
+                    None => {}
+                }
+
                 ui.add(
-                    Slider::new(&mut self.selected_item, 0..=compute_state.iter_count() - 1)
-                        .text("Iteration"),
+                    Slider::new(&mut item, 0..=compute_state.iter_count() - 1).text("Iteration"),
                 );
 
                 egui::ComboBox::from_label("Iterations")
-                    .selected_text(format!("Iteration {}", self.selected_item))
+                    .selected_text(format!("Iteration {item}"))
                     .show_ui(ui, |ui| {
                         for i in 0..compute_state.iter_count() {
-                            ui.selectable_value(
-                                &mut self.selected_item,
-                                i,
-                                format!("Iteration {i}"),
-                            );
+                            ui.selectable_value(&mut item, i, format!("Iteration {i}"));
                         }
                     });
 
+                let waves = &compute_state.get().waves;
+                if !waves.is_empty() {
+                    let wave_text = |i: usize| {
+                        let Wave { kind, failures } = &waves[i];
+                        format!("Wave {i}: {kind}, {} nodes", failures.len())
+                    };
+
+                    egui::ComboBox::from_label("Cascade")
+                        .selected_text(wave.map_or("All waves".to_owned(), wave_text))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut wave, None, "All waves");
+                            for i in 0..waves.len() {
+                                ui.selectable_value(&mut wave, Some(i), wave_text(i));
+                            }
+                        });
+
+                    if let Some(i) = wave {
+                        let nodes = waves[i]
+                            .failures
+                            .iter()
+                            .map(|failure| failure.node.as_idx().to_string())
+                            .collect::<Vec<_>>();
+                        ui.add(Label::new(format!("Deleted: {}", nodes.join(", "))));
+                    }
+                }
+
+                ui.checkbox(&mut show_failed, "Show failed nodes");
+
                 TableBuilder::new(ui)
                     .striped(true)
-                    .columns(Column::auto(), 4)
+                    .columns(Column::auto(), 6)
                     .header(40.0, |mut header| {
                         header.col(|ui| {
                             ui.strong("Node Idx");
@@ -78,9 +258,15 @@ impl UiState {
                         header.col(|ui| {
                             ui.strong("Capacity");
                         });
+                        header.col(|ui| {
+                            ui.strong("Component");
+                        });
+                        header.col(|ui| {
+                            ui.strong("Failed");
+                        });
                     })
                     .body(|mut body| {
-                        let Iteration { graph, info } = app_state.compute.state().get();
+                        let Iteration { graph, info, .. } = compute_state.get();
                         for i in graph.tracker.iter_alive() {
                             body.row(30.0, |mut row| {
                                 if let Some(selected_node) = selected_node {
@@ -105,16 +291,74 @@ impl UiState {
                                     let c = info.capacity[i];
                                     ui.label(c.to_string());
                                 });
+
+                                row.col(|ui| {
+                                    if info.components.count() > 0 {
+                                        ui.label(info.components.label(i).to_string());
+                                    }
+                                });
+
+                                row.col(|_| {});
+                            });
+                        }
+
+                        if !show_failed {
+                            return;
+                        }
+
+                        let current = compute_state.current_iter();
+                        for (i, failed_at) in history.failed_by(current) {
+                            body.row(30.0, |mut row| {
+                                if let Some(selected_node) = selected_node {
+                                    row.set_selected(selected_node == i);
+                                }
+
+                                row.col(|ui| {
+                                    ui.label(i.as_idx().to_string());
+                                });
+
+                                for _ in 0..4 {
+                                    row.col(|ui| {
+                                        ui.label("-");
+                                    });
+                                }
+
+                                row.col(|ui| {
+                                    let FailedAt {
+                                        iteration,
+                                        wave,
+                                        reason,
+                                    } = failed_at;
+                                    ui.label(format!(
+                                        "{reason} in iteration {iteration}, wave {wave}"
+                                    ));
+                                });
                             });
                         }
                     });
             });
 
-        app_state.compute.state_mut().alpha = alpha;
+        app_state.state.alpha = alpha;
+        app_state.show_failed = show_failed;
+        // Waves belong to the iteration they were picked in.
+        app_state.wave = wave.filter(|_| item == viewed_item);
+        if item != viewed_item {
+            self.selected_item = item;
+        }
+        if pipeline != app_state.pipeline {
+            app_state.pipeline = pipeline;
+            recompute = true;
+        }
+
+        if recompute {
+            app_state.recompute();
+        } else if cancel {
+            app_state.cancel();
+        }
     }
 
     fn show_material_editor(&mut self, egui_ctx: &egui::Context, app_state: &mut AppState) {
-        for i in app_state.compute.state().get().graph.tracker.iter_alive() {
+        for i in app_state.state.get().graph.tracker.iter_alive() {
             app_state.world.materials[i] = self.material;
         }
 
@@ -142,39 +386,336 @@ impl UiState {
             });
     }
 
-    fn show_plots(&self, egui_ctx: &egui::Context, app_state: &mut AppState) {
-        let state = app_state.compute.state();
-
+    fn show_plots(&mut self, egui_ctx: &egui::Context, app_state: &mut AppState) {
         egui::Window::new("Plots")
             .resizable(true)
             .default_open(false)
             .show(egui_ctx, |ui| {
-                let bd_points: egui_plot::PlotPoints<'_> = (0..state.beta_deltas.len())
+                ui.horizontal(|ui| {
+                    ui.add(
+                        DragValue::new(&mut self.ensemble_runs)
+                            .range(1..=1000)
+                            .prefix("Runs: "),
+                    );
+                    ui.add(DragValue::new(&mut self.ensemble_seed).prefix("Seed: "));
+
+                    let idle = app_state.batch.is_none();
+                    if ui.add_enabled(idle, Button::new("Run Ensemble")).clicked() {
+                        app_state.run_ensemble(self.ensemble_runs, self.ensemble_seed);
+                    }
+                });
+                Self::show_batch(ui, app_state);
+
+                let state = &app_state.state;
+                let ensemble = app_state.ensemble.as_ref();
+
+                if let Some(ensemble) = ensemble {
+                    ui.add(Label::new(format!(
+                        "Ensemble of {} runs, seeds {}..={}, band is 5% to 95%",
+                        ensemble.seeds.len(),
+                        ensemble.seeds.first().copied().unwrap_or_default(),
+                        ensemble.seeds.last().copied().unwrap_or_default()
+                    )));
+                }
+
+                let bd_points: PlotPoints<'_> = (0..state.beta_deltas.len())
                     .zip(state.beta_deltas.iter())
+                    .filter(|(_, d)| d.is_finite())
                     .map(|(i, d)| [i as f64, *d])
                     .collect();
 
-                let k_points: egui_plot::PlotPoints<'_> = (0..state.ks.len())
+                let g_points: PlotPoints<'_> = (0..state.giant_component.len())
+                    .zip(state.giant_component.iter())
+                    .map(|(i, g)| [i as f64, *g])
+                    .collect();
+
+                let e_points: PlotPoints<'_> = (0..state.relative_efficiency.len())
+                    .zip(state.relative_efficiency.iter())
+                    .map(|(i, e)| [i as f64, *e])
+                    .collect();
+
+                let k_points: PlotPoints<'_> = (0..state.ks.len())
                     .zip(state.ks.iter())
+                    .filter(|(_, k)| k.is_finite())
                     .map(|(i, k)| [i as f64, *k])
                     .collect();
 
+                let a_points: PlotPoints<'_> = (0..state.iter_count())
+                    .map(|i| {
+                        let graph = &state.at(i).graph;
+                        [i as f64, graph.alive() as f64 / graph.node_count() as f64]
+                    })
+                    .collect();
+
                 egui_plot::Plot::new("Beta Delta")
                     .show_axes(true)
                     .show_grid(true)
                     .show(ui, |ui| {
-                        ui.line(egui_plot::Line::new("Beta Delta Lines", bd_points));
+                        if let Some(ensemble) = ensemble {
+                            Self::band(ui, "Beta Delta Ensemble", &ensemble.beta_deltas);
+                        }
+                        ui.line(Line::new("Beta Delta Lines", bd_points));
+                    });
+
+                egui_plot::Plot::new("Giant Component")
+                    .show_axes(true)
+                    .show_grid(true)
+                    .include_y(0.0)
+                    .include_y(1.0)
+                    .show(ui, |ui| {
+                        if let Some(ensemble) = ensemble {
+                            Self::band(ui, "Giant Component Ensemble", &ensemble.giant_component);
+                        }
+                        ui.line(Line::new("Giant Component Lines", g_points));
+                    });
+
+                egui_plot::Plot::new("Efficiency")
+                    .show_axes(true)
+                    .show_grid(true)
+                    .include_y(0.0)
+                    .include_y(1.0)
+                    .show(ui, |ui| {
+                        ui.line(Line::new("Efficiency Lines", e_points));
+                    });
+
+                egui_plot::Plot::new("Alive Fraction")
+                    .show_axes(true)
+                    .show_grid(true)
+                    .include_y(0.0)
+                    .include_y(1.0)
+                    .show(ui, |ui| {
+                        if let Some(ensemble) = ensemble {
+                            Self::band(ui, "Alive Fraction Ensemble", &ensemble.alive_fraction);
+                        }
+                        ui.line(Line::new("Alive Fraction Lines", a_points));
                     });
 
                 egui_plot::Plot::new("Coefficient")
                     .show_axes(true)
                     .show_grid(true)
                     .show(ui, |ui| {
-                        ui.line(egui_plot::Line::new("Coefficient Lines", k_points));
+                        if let Some(ensemble) = ensemble {
+                            Self::band(ui, "Coefficient Ensemble", &ensemble.ks);
+                        }
+                        ui.line(Line::new("Coefficient Lines", k_points));
                     });
             });
     }
 
+    /// Progress of the running ensemble or sweep, shared by their windows.
+    fn show_batch(ui: &mut egui::Ui, app_state: &mut AppState) {
+        if app_state.batch.is_none() {
+            return;
+        }
+
+        let (finished, total) = app_state.batch_progress;
+        ui.horizontal(|ui| {
+            ui.add(
+                ProgressBar::new(finished as f32 / total.max(1) as f32)
+                    .text(format!("{finished}/{total} runs"))
+                    .animate(true),
+            );
+            if ui.button("Cancel").clicked() {
+                app_state.cancel_batch();
+            }
+        });
+    }
+
+    fn show_sweep(&mut self, egui_ctx: &egui::Context, app_state: &mut AppState) {
+        egui::Window::new("Alpha Sweep")
+            .resizable(true)
+            .default_open(false)
+            .show(egui_ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        DragValue::new(&mut self.sweep_from)
+                            .range(0.0..=self.sweep_to)
+                            .speed(0.05)
+                            .prefix("From: "),
+                    );
+                    ui.add(
+                        DragValue::new(&mut self.sweep_to)
+                            .range(self.sweep_from..=f64::MAX)
+                            .speed(0.05)
+                            .prefix("To: "),
+                    );
+                    ui.add(
+                        DragValue::new(&mut self.sweep_steps)
+                            .range(2..=200)
+                            .prefix("Steps: "),
+                    );
+                    ui.add(
+                        DragValue::new(&mut self.sweep_threshold)
+                            .range(0.0..=1.0)
+                            .speed(0.01)
+                            .prefix("G threshold: "),
+                    );
+
+                    let idle = app_state.batch.is_none();
+                    if ui.add_enabled(idle, Button::new("Run Sweep")).clicked() {
+                        app_state.run_sweep(
+                            self.sweep_from..=self.sweep_to,
+                            self.sweep_steps,
+                            self.sweep_threshold,
+                        );
+                    }
+                });
+                ui.add(Label::new(
+                    "Every alpha runs one attack of the graph's strategy and its cascade",
+                ));
+                Self::show_batch(ui, app_state);
+
+                let Some(sweep) = app_state.sweep.as_ref() else {
+                    return;
+                };
+
+                ui.add(Label::new(match sweep.critical_alpha {
+                    Some(alpha) => format!("Critical alpha = {alpha:.4}"),
+                    None => format!("No transition through G = {}", sweep.threshold),
+                }));
+
+                let g_points: PlotPoints<'_> = sweep
+                    .points
+                    .iter()
+                    .map(|p| [p.alpha, p.giant_component])
+                    .collect();
+
+                let a_points: PlotPoints<'_> = sweep
+                    .points
+                    .iter()
+                    .map(|p| [p.alpha, p.alive_fraction])
+                    .collect();
+
+                egui_plot::Plot::new("G(alpha)")
+                    .show_axes(true)
+                    .show_grid(true)
+                    .include_y(0.0)
+                    .include_y(1.0)
+                    .legend(egui_plot::Legend::default())
+                    .show(ui, |ui| {
+                        ui.line(Line::new("G", g_points));
+                        ui.line(Line::new("Alive Fraction", a_points));
+                        ui.hline(egui_plot::HLine::new("Threshold", sweep.threshold));
+                        if let Some(alpha) = sweep.critical_alpha {
+                            ui.vline(egui_plot::VLine::new("Critical Alpha", alpha));
+                        }
+                    });
+            });
+    }
+
+    fn show_cascade_tree(&mut self, egui_ctx: &egui::Context, app_state: &mut AppState) {
+        egui::Window::new("Cascade Tree")
+            .resizable(true)
+            .default_open(false)
+            .show(egui_ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Export Tree").clicked() {
+                        app_state.export_cascade_tree();
+                    }
+                    if ui.button("Export History").clicked() {
+                        app_state.export_failure_history();
+                    }
+                });
+
+                let selected = app_state.selected_node;
+                let tree = app_state.cascade_tree();
+                if tree.is_empty() {
+                    ui.add(Label::new("No node deleted"));
+                    return;
+                }
+
+                if let Some(node) = selected {
+                    match tree.find(node) {
+                        Some(event) => {
+                            ui.strong(format!("Why {} failed", node.as_idx()));
+                            for i in tree.explain(event) {
+                                ui.add(Label::new(Self::event_text(tree, i)));
+                            }
+                        }
+                        None => {
+                            ui.add(Label::new(format!("{} did not fail", node.as_idx())));
+                        }
+                    }
+                    ui.separator();
+                }
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for root in tree.roots() {
+                        Self::event_node(ui, tree, root);
+                    }
+                });
+            });
+    }
+
+    /// An event with the events it is the first cause of, nested below it.
+    fn event_node(ui: &mut egui::Ui, tree: &CascadeTree, event: usize) {
+        let text = Self::event_text(tree, event);
+        if tree.children(event).next().is_none() {
+            ui.add(Label::new(text));
+            return;
+        }
+
+        egui::CollapsingHeader::new(text)
+            .id_salt(event)
+            .show(ui, |ui| {
+                for child in tree.children(event) {
+                    Self::event_node(ui, tree, child);
+                }
+            });
+    }
+
+    fn event_text(tree: &CascadeTree, event: usize) -> String {
+        let CascadeEvent {
+            node,
+            iteration,
+            wave,
+            cause,
+            load,
+            capacity,
+            load_before,
+            causes,
+        } = &tree.events()[event];
+
+        let mut text = format!(
+            "{}: {cause} in iteration {iteration}, wave {wave}, load {load:.2} (from {load_before:.2}) / capacity {capacity:.2}",
+            node.as_idx()
+        );
+        if !causes.is_empty() {
+            let causes = causes
+                .iter()
+                .map(|&cause| tree.events()[cause].node.as_idx().to_string())
+                .collect::<Vec<_>>();
+            text.push_str(&format!(", after {}", causes.join(", ")));
+        }
+        text
+    }
+
+    /// Mean of an ensemble metric with its 5%-95% band. The band is drawn as one quad
+    /// per step since plot polygons are only filled correctly when convex.
+    fn band(ui: &mut PlotUi<'_>, name: &str, stats: &[Stats]) {
+        for (i, pair) in stats.windows(2).enumerate() {
+            let [a, b] = [pair[0], pair[1]];
+            if a.runs == 0 || b.runs == 0 {
+                continue;
+            }
+
+            let (x0, x1) = (i as f64, i as f64 + 1.0);
+            ui.polygon(
+                Polygon::new(name, vec![[x0, a.p5], [x1, b.p5], [x1, b.p95], [x0, a.p95]])
+                    .fill_color(BAND_COLOR.gamma_multiply(0.25))
+                    .stroke((0.0, Color32::TRANSPARENT)),
+            );
+        }
+
+        let mean: PlotPoints<'_> = stats
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.runs > 0)
+            .map(|(i, s)| [i as f64, s.mean])
+            .collect();
+        ui.line(Line::new(format!("{name} Mean"), mean).color(BAND_COLOR));
+    }
+
     #[allow(unused)]
     fn show_gizmo(&self, ui: &mut egui::Ui, app_state: &mut AppState) {
         let Some(selected_node) = app_state.selected_node else {
@@ -23,8 +23,11 @@ pub struct Camera {
     movement_speed: f32,
     mouse_sensitivity: f32,
 
+    #[allow(unused)]
     mouse_captured: bool,
+    #[allow(unused)]
     last_mouse_x: f64,
+    #[allow(unused)]
     last_mouse_y: f64,
 }
 
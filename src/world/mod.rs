@@ -1,12 +1,13 @@
 use camera::Camera;
-use cgmath::{Array, Matrix4, Point3, Vector3};
-use log::info;
+use cgmath::{Matrix4, Point3, Vector3};
 
 use crate::{
-    app::{WINDOW_HEIGHT, WINDOW_WIDTH},
-    compute::state::Iteration,
+    compute::{
+        history::FailureHistory,
+        state::{Iteration, WaveKind},
+    },
     graph::{
-        GraphDesc, GraphInfo,
+        GraphDesc,
         node::{NADVec, NodeStatusTracker},
     },
 };
@@ -104,27 +105,28 @@ impl WorldData {
         NADVec::<Material>::new(tracker)
     }
 
-    pub fn update_materials(&mut self, iter: &Iteration) {
-        let Iteration { graph, info } = iter;
+    /// Colours the alive nodes of `iter` by their load and the nodes deleted by iteration
+    /// `current` by why they were deleted.
+    pub fn update_materials(&mut self, iter: &Iteration, history: &FailureHistory, current: usize) {
+        let Iteration { graph, info, .. } = iter;
         for i in graph.tracker.iter_alive() {
             let min = info.betweenness[info.min_betweenness];
             let max = info.betweenness[info.max_betweenness];
 
             self.materials[i].update_albedo(info.betweenness[i], min, max);
         }
+
+        for (i, failed_at) in history.failed_by(current) {
+            self.materials[i].albedo = match failed_at.reason {
+                WaveKind::Attack => (0.1, 0.1, 0.1).into(),
+                WaveKind::Overload => (0.5, 0.5, 0.5).into(),
+            };
+        }
     }
 }
 
 // TODO: kostil
 pub fn mat4_to_vec(mat: Matrix4<f32>) -> Vec<f32> {
     let mat: [[f32; 4]; 4] = mat.into();
-    let mut vec = Vec::with_capacity(16);
-
-    for i in 0..4 {
-        for j in 0..4 {
-            vec.push(mat[i][j]);
-        }
-    }
-
-    vec
+    mat.into_iter().flatten().collect()
 }